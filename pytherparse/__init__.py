@@ -1,12 +1,50 @@
 from .pytherparse_native import (
     parse_packet,
     parse_pcap_file,
+    PcapReader,
+    CaptureInterface,
+    CaptureSection,
+    CaptureStats,
+    PcapWriter,
+    PcapNgWriter,
+    Defragmenter,
+    TcpReassembler,
+    StreamChunk,
+    FlowTable,
+    Flow,
     ParsedPacket,
+    PacketError,
+    PacketBuilder,
     Ethernet2Header,
+    LinuxSllHeader,
     Ipv4Header,
+    Ipv4OptionRecordRoute,
+    Ipv4OptionSourceRoute,
+    Ipv4OptionTimestamp,
+    Ipv4OptionRouterAlert,
+    Ipv4OptionSecurity,
+    Ipv4OptionRaw,
     Ipv6Header,
+    Ipv6HopByHopOptions,
+    Ipv6RoutingHeader,
+    Ipv6FragmentHeader,
+    Ipv6DestinationOptions,
+    Ipv6AuthenticationHeader,
     TcpHeader,
+    TcpOptionMss,
+    TcpOptionWindowScale,
+    TcpOptionSackPermitted,
+    TcpOptionSack,
+    TcpOptionTimestamp,
+    TcpOptionFastOpen,
+    TcpOptionMptcp,
+    TcpOptionRaw,
     UdpHeader,
+    Icmpv4Header,
+    Icmpv6Header,
+    NdpOption,
+    VlanHeader,
+    ArpHeader,
 )
 
 def parse(file_path_or_bytes):
@@ -35,10 +73,48 @@ __all__ = [
     "parse",
     "parse_packet",
     "parse_pcap_file",
+    "PcapReader",
+    "CaptureInterface",
+    "CaptureSection",
+    "CaptureStats",
+    "PcapWriter",
+    "PcapNgWriter",
+    "Defragmenter",
+    "TcpReassembler",
+    "StreamChunk",
+    "FlowTable",
+    "Flow",
     "ParsedPacket",
+    "PacketError",
+    "PacketBuilder",
     "Ethernet2Header",
+    "LinuxSllHeader",
     "Ipv4Header",
+    "Ipv4OptionRecordRoute",
+    "Ipv4OptionSourceRoute",
+    "Ipv4OptionTimestamp",
+    "Ipv4OptionRouterAlert",
+    "Ipv4OptionSecurity",
+    "Ipv4OptionRaw",
     "Ipv6Header",
+    "Ipv6HopByHopOptions",
+    "Ipv6RoutingHeader",
+    "Ipv6FragmentHeader",
+    "Ipv6DestinationOptions",
+    "Ipv6AuthenticationHeader",
     "TcpHeader",
+    "TcpOptionMss",
+    "TcpOptionWindowScale",
+    "TcpOptionSackPermitted",
+    "TcpOptionSack",
+    "TcpOptionTimestamp",
+    "TcpOptionFastOpen",
+    "TcpOptionMptcp",
+    "TcpOptionRaw",
     "UdpHeader",
+    "Icmpv4Header",
+    "Icmpv6Header",
+    "NdpOption",
+    "VlanHeader",
+    "ArpHeader",
 ]
\ No newline at end of file
@@ -0,0 +1,314 @@
+use std::net::{Ipv4Addr, Ipv6Addr};
+use pyo3::prelude::*;
+use crate::flows::tcp_state::TcpConnection;
+use crate::models::ParsedPacket;
+
+/// Addresses, ports and protocol of an IP packet in the direction it was sent
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub(crate) struct Endpoints {
+    pub protocol: u8,
+    pub source: Vec<u8>,
+    pub source_port: u16,
+    pub destination: Vec<u8>,
+    pub destination_port: u16,
+}
+
+impl Endpoints {
+    /// Read the endpoints of a packet, if it is an IP packet
+    ///
+    /// Ports are 0 for protocols other than TCP and UDP.
+    pub(crate) fn of(packet: &ParsedPacket) -> Option<Self> {
+        let (protocol, source, destination) = match (&packet.ipv4, &packet.ipv6) {
+            (Some(ipv4), _) => (ipv4.protocol(), ipv4.source().to_vec(), ipv4.destination().to_vec()),
+            (None, Some(ipv6)) => (ipv6.upper_layer_protocol(), ipv6.source().to_vec(), ipv6.destination().to_vec()),
+            (None, None) => return None,
+        };
+        let (source_port, destination_port) = match (&packet.tcp, &packet.udp) {
+            (Some(tcp), _) => (tcp.source_port(), tcp.destination_port()),
+            (None, Some(udp)) => (udp.source_port(), udp.destination_port()),
+            (None, None) => (0, 0),
+        };
+        Some(Self { protocol, source, source_port, destination, destination_port })
+    }
+
+    /// Get the key shared by both directions of the conversation
+    pub(crate) fn key(&self) -> Self {
+        if (&self.source, self.source_port) <= (&self.destination, self.destination_port) {
+            self.clone()
+        } else {
+            self.reversed()
+        }
+    }
+
+    /// Get the endpoints of a reply
+    pub(crate) fn reversed(&self) -> Self {
+        Self {
+            protocol: self.protocol,
+            source: self.destination.clone(),
+            source_port: self.destination_port,
+            destination: self.source.clone(),
+            destination_port: self.source_port,
+        }
+    }
+}
+
+/// A bidirectional flow of packets between two endpoints
+///
+/// The source is the endpoint that sent the first packet of the flow.
+/// Forward counters cover the packets from the source to the destination,
+/// reverse counters the packets coming back. Byte counts are IP packet
+/// lengths, so they don't depend on the link layer or the snaplen of the
+/// capture.
+///
+/// TCP flows also follow the state of the connection: the client (the side
+/// that sent the SYN) and the server each go through the RFC 9293 states
+/// from SYN_SENT to CLOSED, and the three-way handshake is timed.
+#[pyclass]
+#[derive(Clone)]
+pub struct Flow {
+    /// IP protocol number (6 for TCP, 17 for UDP)
+    #[pyo3(get)]
+    pub protocol: u8,
+
+    /// Address of the endpoint that sent the first packet
+    #[pyo3(get)]
+    pub source: Vec<u8>,
+
+    /// Port of the endpoint that sent the first packet (0 without ports)
+    #[pyo3(get)]
+    pub source_port: u16,
+
+    /// Address of the other endpoint
+    #[pyo3(get)]
+    pub destination: Vec<u8>,
+
+    /// Port of the other endpoint (0 without ports)
+    #[pyo3(get)]
+    pub destination_port: u16,
+
+    /// Number of packets from the source to the destination
+    #[pyo3(get)]
+    pub forward_packets: u64,
+
+    /// Number of bytes from the source to the destination
+    #[pyo3(get)]
+    pub forward_bytes: u64,
+
+    /// Number of packets from the destination to the source
+    #[pyo3(get)]
+    pub reverse_packets: u64,
+
+    /// Number of bytes from the destination to the source
+    #[pyo3(get)]
+    pub reverse_bytes: u64,
+
+    /// Capture timestamp of the first packet
+    #[pyo3(get)]
+    pub first_seen: f64,
+
+    /// Capture timestamp of the last packet
+    #[pyo3(get)]
+    pub last_seen: f64,
+
+    /// TCP flags sent by the source, OR'ed together (FIN=0x01, SYN=0x02,
+    /// RST=0x04, PSH=0x08, ACK=0x10, URG=0x20, ECE=0x40, CWR=0x80)
+    #[pyo3(get)]
+    pub forward_tcp_flags: u8,
+
+    /// TCP flags sent by the destination, OR'ed together
+    #[pyo3(get)]
+    pub reverse_tcp_flags: u8,
+
+    /// Why the flow ended: "idle", "active", "reused" (a new TCP connection
+    /// started on the ports of a closed one) or "flushed" (None while active)
+    #[pyo3(get)]
+    pub end_reason: Option<&'static str>,
+
+    // Connection state of TCP flows
+    pub(crate) tcp: Option<TcpConnection>,
+}
+
+#[pymethods]
+impl Flow {
+    /// Get the number of packets in both directions
+    #[getter]
+    pub fn packets(&self) -> u64 {
+        self.forward_packets + self.reverse_packets
+    }
+
+    /// Get the number of bytes in both directions
+    #[getter]
+    pub fn bytes(&self) -> u64 {
+        self.forward_bytes + self.reverse_bytes
+    }
+
+    /// Get the seconds between the first and the last packet
+    #[getter]
+    pub fn duration(&self) -> f64 {
+        self.last_seen - self.first_seen
+    }
+
+    /// Get the TCP flags sent in both directions, OR'ed together
+    #[getter]
+    pub fn tcp_flags(&self) -> u8 {
+        self.forward_tcp_flags | self.reverse_tcp_flags
+    }
+
+    /// Get the TCP state of the client, e.g. "ESTABLISHED" (None if not TCP)
+    ///
+    /// The client is the flow's source, unless the capture started with the
+    /// server's SYN-ACK. Connections picked up after the handshake start out
+    /// as "ESTABLISHED".
+    #[getter]
+    pub fn client_state(&self) -> Option<&'static str> {
+        self.tcp.as_ref().map(|tcp| tcp.states[0].name())
+    }
+
+    /// Get the TCP state of the server (None if not TCP)
+    #[getter]
+    pub fn server_state(&self) -> Option<&'static str> {
+        self.tcp.as_ref().map(|tcp| tcp.states[1].name())
+    }
+
+    /// Check whether the client is the flow's source (None if not TCP)
+    #[getter]
+    pub fn client_is_source(&self) -> Option<bool> {
+        self.tcp.as_ref().map(|tcp| tcp.client_is_source)
+    }
+
+    /// Check whether the TCP handshake started but never completed, as
+    /// with unanswered SYNs or SYN floods
+    #[getter]
+    pub fn half_open(&self) -> bool {
+        self.tcp.as_ref().is_some_and(TcpConnection::is_half_open)
+    }
+
+    /// Check whether the TCP connection was reset
+    #[getter]
+    pub fn reset(&self) -> bool {
+        self.reset_by().is_some()
+    }
+
+    /// Get the side that reset the TCP connection: "client", "server" or None
+    #[getter]
+    pub fn reset_by(&self) -> Option<&'static str> {
+        let by_client = self.tcp.as_ref()?.reset_by_client?;
+        Some(if by_client { "client" } else { "server" })
+    }
+
+    /// Get the seconds from the SYN to the SYN-ACK, or None if not seen
+    ///
+    /// With the capture taken at the client this is the round trip time
+    /// to the server. A SYN retransmitted before the SYN-ACK restarts the
+    /// clock.
+    #[getter]
+    pub fn syn_rtt(&self) -> Option<f64> {
+        let tcp = self.tcp.as_ref()?;
+        Some(tcp.syn_ack_time? - tcp.syn_time?)
+    }
+
+    /// Get the seconds from the SYN-ACK to the ACK completing the
+    /// handshake, or None if not seen
+    ///
+    /// With the capture taken at the server this is the round trip time
+    /// to the client.
+    #[getter]
+    pub fn ack_rtt(&self) -> Option<f64> {
+        let tcp = self.tcp.as_ref()?;
+        Some(tcp.ack_time? - tcp.syn_ack_time?)
+    }
+
+    /// Get the seconds from the SYN to the ACK completing the handshake,
+    /// the full round trip time wherever the capture was taken, or None if
+    /// the handshake wasn't seen
+    #[getter]
+    pub fn handshake_rtt(&self) -> Option<f64> {
+        let tcp = self.tcp.as_ref()?;
+        Some(tcp.ack_time? - tcp.syn_time?)
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "Flow(protocol={}, {} -> {}, packets={}, bytes={})",
+            self.protocol,
+            format_endpoint(&self.source, self.source_port),
+            format_endpoint(&self.destination, self.destination_port),
+            self.packets(),
+            self.bytes()
+        )
+    }
+}
+
+impl Flow {
+    /// Start a flow with the endpoints of its first packet
+    pub(crate) fn new(endpoints: &Endpoints, timestamp: f64) -> Self {
+        Self {
+            protocol: endpoints.protocol,
+            source: endpoints.source.clone(),
+            source_port: endpoints.source_port,
+            destination: endpoints.destination.clone(),
+            destination_port: endpoints.destination_port,
+            forward_packets: 0,
+            forward_bytes: 0,
+            reverse_packets: 0,
+            reverse_bytes: 0,
+            first_seen: timestamp,
+            last_seen: timestamp,
+            forward_tcp_flags: 0,
+            reverse_tcp_flags: 0,
+            end_reason: None,
+            tcp: None,
+        }
+    }
+
+    /// Check whether a packet opens a new TCP connection on the ports of
+    /// this one after it closed
+    pub(crate) fn is_reused_by(&self, packet: &ParsedPacket) -> bool {
+        let opens = packet.tcp.as_ref().is_some_and(|tcp| tcp.syn() && !tcp.ack());
+        opens && self.tcp.as_ref().is_some_and(TcpConnection::is_closed)
+    }
+
+    /// Check whether a packet with these endpoints was sent by the source
+    pub(crate) fn is_forward(&self, endpoints: &Endpoints) -> bool {
+        endpoints.source == self.source && endpoints.source_port == self.source_port
+    }
+
+    /// Count a packet of the flow
+    pub(crate) fn update(&mut self, packet: &ParsedPacket, forward: bool) {
+        let length = match (&packet.ipv4, &packet.ipv6) {
+            (Some(ipv4), _) => u64::from(ipv4.total_length()),
+            (None, Some(ipv6)) => 40 + u64::from(ipv6.payload_length()),
+            (None, None) => 0,
+        };
+        let tcp_flags = packet.tcp.as_ref().map_or(0, |tcp| tcp.flags());
+        let timestamp = packet.timestamp();
+        if let Some(tcp) = &packet.tcp {
+            self.tcp
+                .get_or_insert_with(|| TcpConnection::new(tcp))
+                .update(tcp, packet.ip_payload().len(), forward, timestamp);
+        }
+        if forward {
+            self.forward_packets += 1;
+            self.forward_bytes += length;
+            self.forward_tcp_flags |= tcp_flags;
+        } else {
+            self.reverse_packets += 1;
+            self.reverse_bytes += length;
+            self.reverse_tcp_flags |= tcp_flags;
+        }
+
+        self.first_seen = self.first_seen.min(timestamp);
+        self.last_seen = self.last_seen.max(timestamp);
+    }
+}
+
+/// Format an address and port the way tools print them, e.g. "[::1]:53"
+fn format_endpoint(address: &[u8], port: u16) -> String {
+    if let Ok(octets) = <[u8; 4]>::try_from(address) {
+        format!("{}:{}", Ipv4Addr::from(octets), port)
+    } else if let Ok(octets) = <[u8; 16]>::try_from(address) {
+        format!("[{}]:{}", Ipv6Addr::from(octets), port)
+    } else {
+        format!("{:?}:{}", address, port)
+    }
+}
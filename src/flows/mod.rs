@@ -0,0 +1,33 @@
+//! Flow tracking
+//!
+//! This module contains classes that group packets into bidirectional
+//! flows keyed on addresses, ports and protocol.
+
+use pyo3::prelude::*;
+
+// Declare submodules
+mod flow;
+mod table;
+mod tcp_state;
+
+// Re-export flow classes
+pub use flow::Flow;
+pub(crate) use flow::Endpoints;
+pub use table::FlowTable;
+
+/// Seconds of capture time between sweeps for flows and streams that timed out
+pub(crate) const SWEEP_INTERVAL: f64 = 1.0;
+
+/// Check a timeout passed from Python
+///
+/// Raises:
+///     ValueError: If the timeout is negative or NaN
+pub(crate) fn check_timeout(name: &str, timeout: f64) -> PyResult<f64> {
+    if timeout.is_nan() || timeout < 0.0 {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "Invalid {}: {} (expected a number of seconds >= 0)",
+            name, timeout
+        )));
+    }
+    Ok(timeout)
+}
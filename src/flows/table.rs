@@ -0,0 +1,159 @@
+use std::collections::{HashMap, VecDeque};
+use pyo3::prelude::*;
+use crate::flows::{check_timeout, Endpoints, Flow, SWEEP_INTERVAL};
+use crate::models::ParsedPacket;
+
+/// Groups packets into bidirectional flows
+///
+/// Packets are matched on protocol, addresses and ports in either
+/// direction, so a request and its reply land in the same Flow. Feed
+/// packets in capture order with add() and collect the flows that ended
+/// with expired():
+///
+///     table = FlowTable(idle_timeout=60, active_timeout=1800)
+///     for packet in PcapReader("capture.pcap"):
+///         table.add(packet)
+///         for flow in table.expired():
+///             ...
+///     for flow in table.flush():
+///         ...
+///
+/// A flow ends when no packet was seen for idle_timeout seconds, or once it
+/// has lasted active_timeout seconds, after which its next packet starts a
+/// new flow like NetFlow exporters do. Timeouts follow packet timestamps,
+/// not the wall clock. A SYN on the ports of a TCP connection that was
+/// closed or reset also starts a new flow, even within idle_timeout.
+///
+/// Non-first IP fragments carry no ports and are counted in a flow of their
+/// own; put packets through a Defragmenter first to avoid that.
+///
+/// Args:
+///     idle_timeout: Seconds without packets after which a flow ends
+///         (default: 60)
+///     active_timeout: Seconds after which a long flow ends (default: 1800)
+///
+/// Raises:
+///     ValueError: If a timeout is negative
+#[pyclass]
+pub struct FlowTable {
+    idle_timeout: f64,
+    active_timeout: f64,
+    flows: HashMap<Endpoints, Flow>,
+    // Flows that ended and weren't collected yet, in the order they ended
+    expired: VecDeque<Flow>,
+    next_sweep: f64,
+}
+
+#[pymethods]
+impl FlowTable {
+    #[new]
+    #[pyo3(signature = (idle_timeout = 60.0, active_timeout = 1800.0))]
+    pub fn new(idle_timeout: f64, active_timeout: f64) -> PyResult<Self> {
+        Ok(Self {
+            idle_timeout: check_timeout("idle_timeout", idle_timeout)?,
+            active_timeout: check_timeout("active_timeout", active_timeout)?,
+            flows: HashMap::new(),
+            expired: VecDeque::new(),
+            next_sweep: f64::NEG_INFINITY,
+        })
+    }
+
+    /// Count a packet in its flow
+    ///
+    /// Args:
+    ///     packet: The next ParsedPacket in capture order
+    ///
+    /// Returns:
+    ///     bool: True if the packet was counted, False if it isn't an IP packet
+    pub fn add(&mut self, packet: &ParsedPacket) -> bool {
+        let endpoints = match Endpoints::of(packet) {
+            Some(endpoints) => endpoints,
+            None => return false,
+        };
+        let now = packet.timestamp();
+        if now >= self.next_sweep {
+            self.sweep(now);
+            self.next_sweep = now + SWEEP_INTERVAL;
+        }
+
+        let key = endpoints.key();
+        let ended = self.flows.get(&key).and_then(|flow| {
+            self.timeout_reason(flow, now).or_else(|| flow.is_reused_by(packet).then_some("reused"))
+        });
+        if let Some(reason) = ended {
+            if let Some(flow) = self.flows.remove(&key) {
+                self.end(flow, reason);
+            }
+        }
+        let flow = self.flows.entry(key).or_insert_with(|| Flow::new(&endpoints, now));
+        let forward = flow.is_forward(&endpoints);
+        flow.update(packet, forward);
+        true
+    }
+
+    /// Take the flows that ended since the last call
+    ///
+    /// Returns:
+    ///     list[Flow]: The ended flows, in the order they ended
+    pub fn expired(&mut self) -> Vec<Flow> {
+        self.expired.drain(..).collect()
+    }
+
+    /// End all active flows, e.g. at the end of a capture
+    ///
+    /// Returns:
+    ///     list[Flow]: The flows that ended before and weren't taken with
+    ///         expired() yet, followed by all active flows
+    pub fn flush(&mut self) -> Vec<Flow> {
+        let mut flows: Vec<Flow> = self.flows.drain().map(|(_, flow)| flow).collect();
+        flows.sort_by(|a, b| a.first_seen.total_cmp(&b.first_seen));
+        for flow in flows {
+            self.end(flow, "flushed");
+        }
+        self.expired()
+    }
+
+    /// Get the active flows, oldest first
+    #[getter]
+    pub fn flows(&self) -> Vec<Flow> {
+        let mut flows: Vec<Flow> = self.flows.values().cloned().collect();
+        flows.sort_by(|a, b| a.first_seen.total_cmp(&b.first_seen));
+        flows
+    }
+
+    pub fn __len__(&self) -> usize {
+        self.flows.len()
+    }
+}
+
+impl FlowTable {
+    /// Decide whether a flow has ended by the given time, and why
+    fn timeout_reason(&self, flow: &Flow, now: f64) -> Option<&'static str> {
+        if now - flow.last_seen > self.idle_timeout {
+            Some("idle")
+        } else if now - flow.first_seen > self.active_timeout {
+            Some("active")
+        } else {
+            None
+        }
+    }
+
+    /// End every flow that timed out by the given time
+    fn sweep(&mut self, now: f64) {
+        let mut ended: Vec<(Endpoints, &'static str)> = self.flows
+            .iter()
+            .filter_map(|(key, flow)| self.timeout_reason(flow, now).map(|reason| (key.clone(), reason)))
+            .collect();
+        ended.sort_by(|(a, _), (b, _)| self.flows[a].last_seen.total_cmp(&self.flows[b].last_seen));
+        for (key, reason) in ended {
+            if let Some(flow) = self.flows.remove(&key) {
+                self.end(flow, reason);
+            }
+        }
+    }
+
+    fn end(&mut self, mut flow: Flow, reason: &'static str) {
+        flow.end_reason = Some(reason);
+        self.expired.push_back(flow);
+    }
+}
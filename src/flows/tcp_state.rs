@@ -0,0 +1,315 @@
+use crate::headers::TcpHeader;
+
+/// State of one TCP endpoint (RFC 9293, section 3.3.2)
+///
+/// A passive observer only sees packets, so an endpoint moves to the next
+/// state when the packet causing the move shows up in the capture.
+/// TIME-WAIT is never left, as the timer ending it sends no packet.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum TcpState {
+    Listen,
+    SynSent,
+    SynReceived,
+    Established,
+    FinWait1,
+    FinWait2,
+    CloseWait,
+    Closing,
+    LastAck,
+    TimeWait,
+    Closed,
+}
+
+impl TcpState {
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            Self::Listen => "LISTEN",
+            Self::SynSent => "SYN_SENT",
+            Self::SynReceived => "SYN_RECEIVED",
+            Self::Established => "ESTABLISHED",
+            Self::FinWait1 => "FIN_WAIT_1",
+            Self::FinWait2 => "FIN_WAIT_2",
+            Self::CloseWait => "CLOSE_WAIT",
+            Self::Closing => "CLOSING",
+            Self::LastAck => "LAST_ACK",
+            Self::TimeWait => "TIME_WAIT",
+            Self::Closed => "CLOSED",
+        }
+    }
+}
+
+// Index of each side in the per-side arrays
+const CLIENT: usize = 0;
+const SERVER: usize = 1;
+
+/// Follows a TCP connection through its states from the packets of both
+/// sides, timing the three-way handshake on the way
+#[derive(Clone)]
+pub(crate) struct TcpConnection {
+    // Whether the client, the side that sent the SYN, is the flow's source
+    pub client_is_source: bool,
+    // States of the client and the server
+    pub states: [TcpState; 2],
+    // Acknowledgment number that acknowledges each side's FIN
+    fin_acks: [Option<u32>; 2],
+    // Initial sequence number of the server, from the SYN-ACK
+    server_isn: Option<u32>,
+    // Capture times of the last SYN before the SYN-ACK, the first SYN-ACK
+    // and the ACK completing the handshake
+    pub syn_time: Option<f64>,
+    pub syn_ack_time: Option<f64>,
+    pub ack_time: Option<f64>,
+    // Whether the client sent the first reset, if there was one
+    pub reset_by_client: Option<bool>,
+}
+
+impl TcpConnection {
+    /// Start tracking with the first packet of a flow, which is forward
+    ///
+    /// Without a SYN or SYN-ACK the capture started after the handshake,
+    /// and the connection is taken as established.
+    pub(crate) fn new(tcp: &TcpHeader) -> Self {
+        let states = if tcp.syn() {
+            [TcpState::Closed, TcpState::Listen]
+        } else {
+            [TcpState::Established; 2]
+        };
+        Self {
+            client_is_source: !(tcp.syn() && tcp.ack()),
+            states,
+            fin_acks: [None; 2],
+            server_isn: None,
+            syn_time: None,
+            syn_ack_time: None,
+            ack_time: None,
+            reset_by_client: None,
+        }
+    }
+
+    /// Move the states along with a packet of the connection
+    pub(crate) fn update(&mut self, tcp: &TcpHeader, payload_len: usize, forward: bool, timestamp: f64) {
+        let sender = if forward == self.client_is_source { CLIENT } else { SERVER };
+        let receiver = 1 - sender;
+        if tcp.rst() {
+            self.states = [TcpState::Closed; 2];
+            self.reset_by_client.get_or_insert(sender == CLIENT);
+            return;
+        }
+
+        // Handshake
+        if tcp.syn() && !tcp.ack() && sender == CLIENT && matches!(self.states[CLIENT], TcpState::Closed | TcpState::SynSent) {
+            self.states[CLIENT] = TcpState::SynSent;
+            // SYNs retransmitted after the SYN-ACK didn't cause it
+            if self.syn_ack_time.is_none() {
+                self.syn_time = Some(timestamp);
+            }
+        }
+        if tcp.syn() && tcp.ack() && sender == SERVER && matches!(self.states[SERVER], TcpState::Listen | TcpState::SynReceived) {
+            self.states[SERVER] = TcpState::SynReceived;
+            self.server_isn = Some(tcp.sequence_number());
+            self.syn_ack_time.get_or_insert(timestamp);
+        }
+        if tcp.ack() && !tcp.syn() && sender == CLIENT && self.states[SERVER] == TcpState::SynReceived
+            && self.server_isn.map(|isn| isn.wrapping_add(1)) == Some(tcp.acknowledgment_number())
+        {
+            self.states = [TcpState::Established; 2];
+            self.ack_time = Some(timestamp);
+        }
+
+        // The receiver's FIN being acknowledged
+        let fin_acked = self.acknowledges_fin(tcp, receiver);
+        if fin_acked {
+            self.states[receiver] = match self.states[receiver] {
+                TcpState::FinWait1 => TcpState::FinWait2,
+                TcpState::Closing => TcpState::TimeWait,
+                TcpState::LastAck => TcpState::Closed,
+                state => state,
+            };
+        }
+
+        // The sender closing its side
+        if tcp.fin() && self.fin_acks[sender].is_none() {
+            let fin_seq = tcp.sequence_number()
+                .wrapping_add(u32::from(tcp.syn()))
+                .wrapping_add(payload_len as u32);
+            self.fin_acks[sender] = Some(fin_seq.wrapping_add(1));
+            match self.states[sender] {
+                TcpState::Established | TcpState::SynReceived => {
+                    self.states[sender] = TcpState::FinWait1;
+                    if self.states[receiver] == TcpState::Established {
+                        self.states[receiver] = TcpState::CloseWait;
+                    }
+                },
+                // Closing after the receiver's FIN arrived
+                TcpState::CloseWait if fin_acked => {
+                    self.states[sender] = TcpState::LastAck;
+                    if self.states[receiver] == TcpState::FinWait2 {
+                        self.states[receiver] = TcpState::TimeWait;
+                    }
+                },
+                // Both FINs crossed on the wire: a simultaneous close
+                TcpState::CloseWait => {
+                    self.states[sender] = TcpState::Closing;
+                    if self.states[receiver] == TcpState::FinWait1 {
+                        self.states[receiver] = TcpState::Closing;
+                    }
+                },
+                _ => {},
+            }
+        }
+    }
+
+    /// Check whether a segment acknowledges the FIN of the given side
+    fn acknowledges_fin(&self, tcp: &TcpHeader, side: usize) -> bool {
+        self.fin_acks[side]
+            .is_some_and(|fin_ack| tcp.ack() && tcp.acknowledgment_number().wrapping_sub(fin_ack) as i32 >= 0)
+    }
+
+    /// Check whether both sides closed the connection or it was reset
+    pub(crate) fn is_closed(&self) -> bool {
+        self.states.iter().all(|state| matches!(state, TcpState::Closed | TcpState::TimeWait))
+    }
+
+    /// Check whether the handshake started but didn't complete
+    pub(crate) fn is_half_open(&self) -> bool {
+        self.reset_by_client.is_none()
+            && (self.states[CLIENT] == TcpState::SynSent || self.states[SERVER] == TcpState::SynReceived)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Build a segment with the flags given as letters, e.g. "SA"
+    fn segment(flags: &str, seq: u32, ack: u32) -> TcpHeader {
+        let mut header = etherparse::TcpHeader::default();
+        header.sequence_number = seq;
+        header.acknowledgment_number = ack;
+        header.syn = flags.contains('S');
+        header.ack = flags.contains('A');
+        header.fin = flags.contains('F');
+        header.rst = flags.contains('R');
+        TcpHeader::from(header)
+    }
+
+    /// Track a connection through the three-way handshake, client ISN 100
+    /// and server ISN 500
+    fn established() -> TcpConnection {
+        let mut connection = TcpConnection::new(&segment("S", 100, 0));
+        connection.update(&segment("S", 100, 0), 0, true, 1.0);
+        connection.update(&segment("SA", 500, 101), 0, false, 1.25);
+        connection.update(&segment("A", 101, 501), 0, true, 1.5);
+        connection
+    }
+
+    fn names(connection: &TcpConnection) -> [&'static str; 2] {
+        connection.states.map(TcpState::name)
+    }
+
+    #[test]
+    fn handshake_records_its_times() {
+        let mut connection = TcpConnection::new(&segment("S", 100, 0));
+        connection.update(&segment("S", 100, 0), 0, true, 1.0);
+        assert_eq!(names(&connection), ["SYN_SENT", "LISTEN"]);
+        connection.update(&segment("SA", 500, 101), 0, false, 1.25);
+        assert_eq!(names(&connection), ["SYN_SENT", "SYN_RECEIVED"]);
+        assert!(connection.is_half_open());
+        connection.update(&segment("A", 101, 501), 0, true, 1.5);
+        assert_eq!(names(&connection), ["ESTABLISHED", "ESTABLISHED"]);
+        assert!(!connection.is_half_open());
+        assert_eq!(
+            (connection.syn_time, connection.syn_ack_time, connection.ack_time),
+            (Some(1.0), Some(1.25), Some(1.5))
+        );
+    }
+
+    #[test]
+    fn syn_retransmitted_after_the_syn_ack_keeps_the_syn_time() {
+        let mut connection = TcpConnection::new(&segment("S", 100, 0));
+        connection.update(&segment("S", 100, 0), 0, true, 1.0);
+        connection.update(&segment("S", 100, 0), 0, true, 2.0);
+        connection.update(&segment("SA", 500, 101), 0, false, 2.25);
+        connection.update(&segment("S", 100, 0), 0, true, 3.0);
+        assert_eq!(connection.syn_time, Some(2.0));
+        assert_eq!(connection.syn_ack_time, Some(2.25));
+    }
+
+    #[test]
+    fn ack_of_another_isn_does_not_complete_the_handshake() {
+        let mut connection = TcpConnection::new(&segment("S", 100, 0));
+        connection.update(&segment("S", 100, 0), 0, true, 1.0);
+        connection.update(&segment("SA", 500, 101), 0, false, 1.25);
+        connection.update(&segment("A", 101, 9999), 0, true, 1.5);
+        assert_eq!(names(&connection), ["SYN_SENT", "SYN_RECEIVED"]);
+        assert_eq!(connection.ack_time, None);
+    }
+
+    #[test]
+    fn client_close_ends_in_time_wait() {
+        let mut connection = established();
+        connection.update(&segment("FA", 101, 501), 0, true, 2.0);
+        assert_eq!(names(&connection), ["FIN_WAIT_1", "CLOSE_WAIT"]);
+        connection.update(&segment("A", 501, 102), 0, false, 2.1);
+        assert_eq!(names(&connection), ["FIN_WAIT_2", "CLOSE_WAIT"]);
+        assert!(!connection.is_closed());
+        connection.update(&segment("FA", 501, 102), 0, false, 2.2);
+        assert_eq!(names(&connection), ["TIME_WAIT", "LAST_ACK"]);
+        connection.update(&segment("A", 102, 502), 0, true, 2.3);
+        assert_eq!(names(&connection), ["TIME_WAIT", "CLOSED"]);
+        assert!(connection.is_closed());
+    }
+
+    #[test]
+    fn fin_after_data_is_acknowledged_past_the_data() {
+        let mut connection = established();
+        connection.update(&segment("FA", 101, 501), 10, true, 2.0);
+        connection.update(&segment("A", 501, 102), 0, false, 2.1);
+        assert_eq!(names(&connection), ["FIN_WAIT_1", "CLOSE_WAIT"]);
+        connection.update(&segment("A", 501, 112), 0, false, 2.2);
+        assert_eq!(names(&connection), ["FIN_WAIT_2", "CLOSE_WAIT"]);
+    }
+
+    #[test]
+    fn simultaneous_close_goes_through_closing() {
+        let mut connection = established();
+        // Neither FIN acknowledges the other
+        connection.update(&segment("FA", 101, 501), 0, true, 2.0);
+        connection.update(&segment("FA", 501, 101), 0, false, 2.0);
+        assert_eq!(names(&connection), ["CLOSING", "CLOSING"]);
+        connection.update(&segment("A", 102, 502), 0, true, 2.1);
+        assert_eq!(names(&connection), ["CLOSING", "TIME_WAIT"]);
+        connection.update(&segment("A", 502, 102), 0, false, 2.1);
+        assert_eq!(names(&connection), ["TIME_WAIT", "TIME_WAIT"]);
+        assert!(connection.is_closed());
+    }
+
+    #[test]
+    fn first_reset_decides_who_reset() {
+        let mut connection = established();
+        connection.update(&segment("R", 501, 0), 0, false, 2.0);
+        connection.update(&segment("R", 101, 0), 0, true, 2.1);
+        assert_eq!(names(&connection), ["CLOSED", "CLOSED"]);
+        assert_eq!(connection.reset_by_client, Some(false));
+        assert!(connection.is_closed());
+    }
+
+    #[test]
+    fn reset_handshake_is_not_half_open() {
+        let mut connection = TcpConnection::new(&segment("S", 100, 0));
+        connection.update(&segment("S", 100, 0), 0, true, 1.0);
+        assert!(connection.is_half_open());
+        connection.update(&segment("RA", 0, 101), 0, false, 1.1);
+        assert!(!connection.is_half_open());
+        assert_eq!(connection.reset_by_client, Some(false));
+    }
+
+    #[test]
+    fn syn_ack_seen_first_makes_the_destination_the_client() {
+        let connection = TcpConnection::new(&segment("SA", 500, 101));
+        assert!(!connection.client_is_source);
+        let connection = TcpConnection::new(&segment("A", 101, 501));
+        assert!(connection.client_is_source);
+        assert_eq!(names(&connection), ["ESTABLISHED", "ESTABLISHED"]);
+    }
+}
@@ -0,0 +1,251 @@
+use pyo3::prelude::*;
+
+// ARP operation codes (RFC 826, RFC 903)
+const REQUEST: u16 = 1;
+const REPLY: u16 = 2;
+const REVERSE_REQUEST: u16 = 3;
+const REVERSE_REPLY: u16 = 4;
+
+// Hardware and protocol types of ARP over Ethernet for IPv4
+const HARDWARE_ETHERNET: u16 = 1;
+const PROTOCOL_IPV4: u16 = 0x0800;
+
+/// Python wrapper for an ARP packet
+/// Represents an Address Resolution Protocol message (EtherType 0x0806)
+///
+/// Address lengths follow the hardware and protocol lengths of the message,
+/// so 6-byte MAC and 4-byte IPv4 addresses for ARP over Ethernet.
+#[pyclass]
+#[derive(Clone)]
+pub struct ArpHeader {
+    // etherparse has no ARP support, so the fields are kept here
+    hardware_type: u16,
+    protocol_type: u16,
+    opcode: u16,
+    sender_hardware_address: Vec<u8>,
+    sender_protocol_address: Vec<u8>,
+    target_hardware_address: Vec<u8>,
+    target_protocol_address: Vec<u8>,
+}
+
+#[pymethods]
+impl ArpHeader {
+    /// Create a new ARP message
+    ///
+    /// Args:
+    ///     opcode: Operation (1 = request, 2 = reply)
+    ///     sender_mac: Hardware address of the sender
+    ///     sender_ip: Protocol address of the sender
+    ///     target_mac: Hardware address of the target (zeros in requests)
+    ///     target_ip: Protocol address of the target
+    ///     hardware_type: Hardware type (default: 1, Ethernet)
+    ///     protocol_type: Protocol type (default: 0x0800, IPv4)
+    ///
+    /// Raises:
+    ///     ValueError: If the sender and target addresses differ in length
+    ///         or are longer than 255 bytes
+    #[new]
+    #[pyo3(signature = (opcode, sender_mac, sender_ip, target_mac, target_ip, hardware_type = HARDWARE_ETHERNET, protocol_type = PROTOCOL_IPV4))]
+    pub fn new(
+        opcode: u16,
+        sender_mac: Vec<u8>,
+        sender_ip: Vec<u8>,
+        target_mac: Vec<u8>,
+        target_ip: Vec<u8>,
+        hardware_type: u16,
+        protocol_type: u16,
+    ) -> PyResult<Self> {
+        if sender_mac.len() != target_mac.len() || sender_mac.len() > 255 {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Hardware addresses must have the same length of at most 255 bytes (got {} and {})",
+                sender_mac.len(), target_mac.len()
+            )));
+        }
+        if sender_ip.len() != target_ip.len() || sender_ip.len() > 255 {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Protocol addresses must have the same length of at most 255 bytes (got {} and {})",
+                sender_ip.len(), target_ip.len()
+            )));
+        }
+        Ok(Self {
+            hardware_type,
+            protocol_type,
+            opcode,
+            sender_hardware_address: sender_mac,
+            sender_protocol_address: sender_ip,
+            target_hardware_address: target_mac,
+            target_protocol_address: target_ip,
+        })
+    }
+
+    /// Create a gratuitous ARP request announcing an address
+    ///
+    /// Sender and target IP are both the announced address.
+    ///
+    /// Args:
+    ///     mac: MAC address of the announcing host
+    ///     ip: IPv4 address being announced
+    #[staticmethod]
+    pub fn gratuitous(mac: [u8; 6], ip: [u8; 4]) -> Self {
+        Self::ethernet_ipv4(REQUEST, mac, ip, [0; 6], ip)
+    }
+
+    /// Create an ARP probe (RFC 5227) checking whether an address is in use
+    ///
+    /// The sender IP is 0.0.0.0 so the probe doesn't update other hosts'
+    /// caches.
+    ///
+    /// Args:
+    ///     mac: MAC address of the probing host
+    ///     ip: IPv4 address being probed
+    #[staticmethod]
+    pub fn probe(mac: [u8; 6], ip: [u8; 4]) -> Self {
+        Self::ethernet_ipv4(REQUEST, mac, [0; 4], [0; 6], ip)
+    }
+
+    /// Get the hardware type (1 for Ethernet)
+    #[getter]
+    pub fn hardware_type(&self) -> u16 {
+        self.hardware_type
+    }
+
+    /// Get the protocol type (0x0800 for IPv4)
+    #[getter]
+    pub fn protocol_type(&self) -> u16 {
+        self.protocol_type
+    }
+
+    /// Get the hardware address length in bytes
+    #[getter]
+    pub fn hardware_length(&self) -> u8 {
+        self.sender_hardware_address.len() as u8
+    }
+
+    /// Get the protocol address length in bytes
+    #[getter]
+    pub fn protocol_length(&self) -> u8 {
+        self.sender_protocol_address.len() as u8
+    }
+
+    /// Get the operation code (1 = request, 2 = reply)
+    #[getter]
+    pub fn opcode(&self) -> u16 {
+        self.opcode
+    }
+
+    /// Get the operation as a name
+    ///
+    /// Returns "request", "reply", "reverse_request", "reverse_reply" or
+    /// None for other values.
+    #[getter]
+    pub fn opcode_name(&self) -> Option<&'static str> {
+        match self.opcode {
+            REQUEST => Some("request"),
+            REPLY => Some("reply"),
+            REVERSE_REQUEST => Some("reverse_request"),
+            REVERSE_REPLY => Some("reverse_reply"),
+            _ => None,
+        }
+    }
+
+    /// Get the hardware address of the sender
+    #[getter]
+    pub fn sender_mac(&self) -> Vec<u8> {
+        self.sender_hardware_address.clone()
+    }
+
+    /// Get the protocol address of the sender
+    #[getter]
+    pub fn sender_ip(&self) -> Vec<u8> {
+        self.sender_protocol_address.clone()
+    }
+
+    /// Get the hardware address of the target
+    #[getter]
+    pub fn target_mac(&self) -> Vec<u8> {
+        self.target_hardware_address.clone()
+    }
+
+    /// Get the protocol address of the target
+    #[getter]
+    pub fn target_ip(&self) -> Vec<u8> {
+        self.target_protocol_address.clone()
+    }
+
+    /// Check if this is a gratuitous ARP (sender and target IP are equal)
+    pub fn is_gratuitous(&self) -> bool {
+        self.sender_protocol_address == self.target_protocol_address
+    }
+
+    /// Check if this is an ARP probe (request with an all-zero sender IP)
+    pub fn is_probe(&self) -> bool {
+        self.opcode == REQUEST && self.sender_protocol_address.iter().all(|b| *b == 0)
+    }
+
+    /// Get the length of the message in bytes
+    #[getter]
+    pub fn header_length(&self) -> usize {
+        8 + 2 * (self.sender_hardware_address.len() + self.sender_protocol_address.len())
+    }
+
+    /// Serialize the ARP message to bytes
+    ///
+    /// Returns:
+    ///     A vector of bytes representing the message
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.header_length());
+        buf.extend_from_slice(&self.hardware_type.to_be_bytes());
+        buf.extend_from_slice(&self.protocol_type.to_be_bytes());
+        buf.push(self.hardware_length());
+        buf.push(self.protocol_length());
+        buf.extend_from_slice(&self.opcode.to_be_bytes());
+        buf.extend_from_slice(&self.sender_hardware_address);
+        buf.extend_from_slice(&self.sender_protocol_address);
+        buf.extend_from_slice(&self.target_hardware_address);
+        buf.extend_from_slice(&self.target_protocol_address);
+        buf
+    }
+}
+
+impl ArpHeader {
+    /// Read an ARP message, returning it and the rest of the slice
+    pub(crate) fn from_slice(slice: &[u8]) -> Result<(Self, &[u8]), etherparse::ReadError> {
+        if slice.len() < 8 {
+            return Err(etherparse::ReadError::UnexpectedEndOfSlice(8));
+        }
+        let hardware_length = usize::from(slice[4]);
+        let protocol_length = usize::from(slice[5]);
+        let len = 8 + 2 * (hardware_length + protocol_length);
+        if slice.len() < len {
+            return Err(etherparse::ReadError::UnexpectedEndOfSlice(len));
+        }
+
+        let (sender_hardware_address, rest) = slice[8..].split_at(hardware_length);
+        let (sender_protocol_address, rest) = rest.split_at(protocol_length);
+        let (target_hardware_address, rest) = rest.split_at(hardware_length);
+        let (target_protocol_address, rest) = rest.split_at(protocol_length);
+        let header = Self {
+            hardware_type: u16::from_be_bytes([slice[0], slice[1]]),
+            protocol_type: u16::from_be_bytes([slice[2], slice[3]]),
+            opcode: u16::from_be_bytes([slice[6], slice[7]]),
+            sender_hardware_address: sender_hardware_address.to_vec(),
+            sender_protocol_address: sender_protocol_address.to_vec(),
+            target_hardware_address: target_hardware_address.to_vec(),
+            target_protocol_address: target_protocol_address.to_vec(),
+        };
+        Ok((header, rest))
+    }
+
+    /// Create an ARP message for IPv4 over Ethernet
+    fn ethernet_ipv4(opcode: u16, sender_mac: [u8; 6], sender_ip: [u8; 4], target_mac: [u8; 6], target_ip: [u8; 4]) -> Self {
+        Self {
+            hardware_type: HARDWARE_ETHERNET,
+            protocol_type: PROTOCOL_IPV4,
+            opcode,
+            sender_hardware_address: sender_mac.to_vec(),
+            sender_protocol_address: sender_ip.to_vec(),
+            target_hardware_address: target_mac.to_vec(),
+            target_protocol_address: target_ip.to_vec(),
+        }
+    }
+}
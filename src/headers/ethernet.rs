@@ -1,4 +1,5 @@
 use pyo3::prelude::*;
+use crate::headers::field_value;
 
 /// Python wrapper for etherparse::Ethernet2Header
 /// Represents an Ethernet II frame header
@@ -47,6 +48,28 @@ impl Ethernet2Header {
         self.inner.ether_type 
     }
 
+    /// Set the source MAC address
+    #[setter]
+    pub fn set_source(&mut self, source: [u8; 6]) {
+        self.inner.source = source;
+    }
+
+    /// Set the destination MAC address
+    #[setter]
+    pub fn set_destination(&mut self, destination: [u8; 6]) {
+        self.inner.destination = destination;
+    }
+
+    /// Set the EtherType field
+    ///
+    /// Raises:
+    ///     ValueError: If the value doesn't fit into 16 bits
+    #[setter]
+    pub fn set_ether_type(&mut self, ether_type: i64) -> PyResult<()> {
+        self.inner.ether_type = field_value("EtherType", ether_type, u16::MAX.into())?;
+        Ok(())
+    }
+
     /// Serialize the Ethernet header to bytes
     /// 
     /// Returns:
@@ -0,0 +1,284 @@
+use pyo3::prelude::*;
+use crate::headers::{id_and_sequence, internet_checksum};
+
+// ICMPv4 message types (RFC 792)
+const ECHO_REPLY: u8 = 0;
+const DESTINATION_UNREACHABLE: u8 = 3;
+const REDIRECT: u8 = 5;
+const ECHO_REQUEST: u8 = 8;
+const TIME_EXCEEDED: u8 = 11;
+const PARAMETER_PROBLEM: u8 = 12;
+const TIMESTAMP_REQUEST: u8 = 13;
+const TIMESTAMP_REPLY: u8 = 14;
+
+// Destination unreachable code that carries the next-hop MTU (RFC 1191)
+const FRAGMENTATION_NEEDED: u8 = 4;
+
+/// Python wrapper for an ICMPv4 header
+/// Represents the first 8 bytes of an ICMP message: type, code, checksum
+/// and the type-specific "rest of header"
+///
+/// The message body (echo data or, for error messages, the original
+/// datagram) is not part of the header and ends up in the packet payload.
+#[pyclass]
+#[derive(Clone)]
+pub struct Icmpv4Header {
+    icmp_type: u8,
+    code: u8,
+    checksum: u16,
+    rest_of_header: [u8; 4],
+    // Copy of the message body of error messages, set when parsing
+    original_datagram: Vec<u8>,
+}
+
+#[pymethods]
+impl Icmpv4Header {
+    /// Create a new ICMPv4 header
+    ///
+    /// Args:
+    ///     icmp_type: ICMP message type (e.g. 8 for echo request)
+    ///     code: ICMP message code (default: 0)
+    ///     rest_of_header: The 4 type-specific bytes after the checksum (default: zeros)
+    ///     checksum: Checksum field (default: 0, see calc_checksum)
+    #[new]
+    #[pyo3(signature = (icmp_type, code = 0, rest_of_header = [0; 4], checksum = 0))]
+    pub fn new(icmp_type: u8, code: u8, rest_of_header: [u8; 4], checksum: u16) -> Self {
+        Self {
+            icmp_type,
+            code,
+            checksum,
+            rest_of_header,
+            original_datagram: Vec::new(),
+        }
+    }
+
+    /// Create an echo request (ping) header
+    ///
+    /// Args:
+    ///     identifier: Echo identifier
+    ///     sequence: Echo sequence number
+    #[staticmethod]
+    pub fn echo_request(identifier: u16, sequence: u16) -> Self {
+        Self::new(ECHO_REQUEST, 0, id_and_sequence(identifier, sequence), 0)
+    }
+
+    /// Create an echo reply header
+    ///
+    /// Args:
+    ///     identifier: Echo identifier
+    ///     sequence: Echo sequence number
+    #[staticmethod]
+    pub fn echo_reply(identifier: u16, sequence: u16) -> Self {
+        Self::new(ECHO_REPLY, 0, id_and_sequence(identifier, sequence), 0)
+    }
+
+    /// Create a destination unreachable header
+    ///
+    /// The original datagram (IP header plus at least 8 bytes) follows as
+    /// payload.
+    ///
+    /// Args:
+    ///     code: Unreachable code (e.g. 3 for port unreachable)
+    ///     next_hop_mtu: MTU of the next hop for code 4 (default: 0)
+    #[staticmethod]
+    #[pyo3(signature = (code, next_hop_mtu = 0))]
+    pub fn destination_unreachable(code: u8, next_hop_mtu: u16) -> Self {
+        let mtu = next_hop_mtu.to_be_bytes();
+        Self::new(DESTINATION_UNREACHABLE, code, [0, 0, mtu[0], mtu[1]], 0)
+    }
+
+    /// Create a time exceeded header
+    ///
+    /// The original datagram (IP header plus at least 8 bytes) follows as
+    /// payload.
+    ///
+    /// Args:
+    ///     code: 0 for TTL exceeded in transit, 1 for reassembly time exceeded
+    #[staticmethod]
+    #[pyo3(signature = (code = 0))]
+    pub fn time_exceeded(code: u8) -> Self {
+        Self::new(TIME_EXCEEDED, code, [0; 4], 0)
+    }
+
+    /// Get the ICMP message type
+    #[getter]
+    pub fn icmp_type(&self) -> u8 {
+        self.icmp_type
+    }
+
+    /// Get the ICMP message code
+    #[getter]
+    pub fn code(&self) -> u8 {
+        self.code
+    }
+
+    /// Get the checksum field
+    #[getter]
+    pub fn checksum(&self) -> u16 {
+        self.checksum
+    }
+
+    /// Set the checksum field
+    #[setter]
+    pub fn set_checksum(&mut self, checksum: u16) {
+        self.checksum = checksum;
+    }
+
+    /// Get the 4 type-specific bytes after the checksum
+    #[getter]
+    pub fn rest_of_header(&self) -> [u8; 4] {
+        self.rest_of_header
+    }
+
+    /// Get the name of the message type
+    ///
+    /// Returns "echo_reply", "destination_unreachable", "redirect",
+    /// "echo_request", "time_exceeded", "parameter_problem",
+    /// "timestamp_request", "timestamp_reply" or None for other types.
+    #[getter]
+    pub fn type_name(&self) -> Option<&'static str> {
+        match self.icmp_type {
+            ECHO_REPLY => Some("echo_reply"),
+            DESTINATION_UNREACHABLE => Some("destination_unreachable"),
+            REDIRECT => Some("redirect"),
+            ECHO_REQUEST => Some("echo_request"),
+            TIME_EXCEEDED => Some("time_exceeded"),
+            PARAMETER_PROBLEM => Some("parameter_problem"),
+            TIMESTAMP_REQUEST => Some("timestamp_request"),
+            TIMESTAMP_REPLY => Some("timestamp_reply"),
+            _ => None,
+        }
+    }
+
+    /// Get the identifier of echo and timestamp messages (None otherwise)
+    #[getter]
+    pub fn identifier(&self) -> Option<u16> {
+        if self.has_id_and_sequence() {
+            Some(u16::from_be_bytes([self.rest_of_header[0], self.rest_of_header[1]]))
+        } else {
+            None
+        }
+    }
+
+    /// Get the sequence number of echo and timestamp messages (None otherwise)
+    #[getter]
+    pub fn sequence(&self) -> Option<u16> {
+        if self.has_id_and_sequence() {
+            Some(u16::from_be_bytes([self.rest_of_header[2], self.rest_of_header[3]]))
+        } else {
+            None
+        }
+    }
+
+    /// Get the next-hop MTU of "fragmentation needed" messages (None otherwise)
+    #[getter]
+    pub fn next_hop_mtu(&self) -> Option<u16> {
+        if self.icmp_type == DESTINATION_UNREACHABLE && self.code == FRAGMENTATION_NEEDED {
+            Some(u16::from_be_bytes([self.rest_of_header[2], self.rest_of_header[3]]))
+        } else {
+            None
+        }
+    }
+
+    /// Get the gateway address of redirect messages (None otherwise)
+    #[getter]
+    pub fn gateway(&self) -> Option<[u8; 4]> {
+        if self.icmp_type == REDIRECT {
+            Some(self.rest_of_header)
+        } else {
+            None
+        }
+    }
+
+    /// Get the pointer to the offending byte of parameter problem messages
+    /// (None otherwise)
+    #[getter]
+    pub fn pointer(&self) -> Option<u8> {
+        if self.icmp_type == PARAMETER_PROBLEM {
+            Some(self.rest_of_header[0])
+        } else {
+            None
+        }
+    }
+
+    /// Check if this is an error message that quotes an original datagram
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self.icmp_type,
+            DESTINATION_UNREACHABLE | REDIRECT | TIME_EXCEEDED | PARAMETER_PROBLEM
+        )
+    }
+
+    /// Get the quoted original datagram of error messages
+    ///
+    /// Holds the IP header and the first bytes of the datagram that caused
+    /// the error. None for other messages and for headers built in Python.
+    #[getter]
+    pub fn original_datagram(&self) -> Option<Vec<u8>> {
+        if self.is_error() && !self.original_datagram.is_empty() {
+            Some(self.original_datagram.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Calculate and set the checksum over the header and the message body
+    ///
+    /// Args:
+    ///     payload: Message body following the header (default: empty)
+    ///
+    /// Returns:
+    ///     int: The new checksum
+    #[pyo3(signature = (payload = Vec::new()))]
+    pub fn calc_checksum(&mut self, payload: Vec<u8>) -> u16 {
+        self.checksum = 0;
+        self.checksum = internet_checksum(&[&self.to_bytes(), &payload]);
+        self.checksum
+    }
+
+    /// Get the header length (always 8 bytes)
+    #[getter]
+    pub fn header_length(&self) -> u8 {
+        8
+    }
+
+    /// Serialize the ICMP header to bytes
+    ///
+    /// Returns:
+    ///     A vector of 8 bytes representing the header
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(8);
+        buf.push(self.icmp_type);
+        buf.push(self.code);
+        buf.extend_from_slice(&self.checksum.to_be_bytes());
+        buf.extend_from_slice(&self.rest_of_header);
+        buf
+    }
+}
+
+impl Icmpv4Header {
+    /// Read an ICMPv4 header, returning it and the message body
+    pub(crate) fn from_slice(slice: &[u8]) -> Result<(Self, &[u8]), etherparse::ReadError> {
+        if slice.len() < 8 {
+            return Err(etherparse::ReadError::UnexpectedEndOfSlice(8));
+        }
+        let mut header = Self::new(
+            slice[0],
+            slice[1],
+            [slice[4], slice[5], slice[6], slice[7]],
+            u16::from_be_bytes([slice[2], slice[3]]),
+        );
+        let body = &slice[8..];
+        if header.is_error() {
+            header.original_datagram = body.to_vec();
+        }
+        Ok((header, body))
+    }
+
+    fn has_id_and_sequence(&self) -> bool {
+        matches!(
+            self.icmp_type,
+            ECHO_REPLY | ECHO_REQUEST | TIMESTAMP_REQUEST | TIMESTAMP_REPLY
+        )
+    }
+}
@@ -0,0 +1,686 @@
+use pyo3::prelude::*;
+use crate::headers::{id_and_sequence, internet_checksum};
+
+// ICMPv6 message types (RFC 4443, RFC 4861)
+const DESTINATION_UNREACHABLE: u8 = 1;
+const PACKET_TOO_BIG: u8 = 2;
+const TIME_EXCEEDED: u8 = 3;
+const PARAMETER_PROBLEM: u8 = 4;
+const ECHO_REQUEST: u8 = 128;
+const ECHO_REPLY: u8 = 129;
+const ROUTER_SOLICITATION: u8 = 133;
+const ROUTER_ADVERTISEMENT: u8 = 134;
+const NEIGHBOR_SOLICITATION: u8 = 135;
+const NEIGHBOR_ADVERTISEMENT: u8 = 136;
+const REDIRECT: u8 = 137;
+
+// NDP option types (RFC 4861)
+const OPT_SOURCE_LINK_LAYER_ADDRESS: u8 = 1;
+const OPT_TARGET_LINK_LAYER_ADDRESS: u8 = 2;
+const OPT_PREFIX_INFORMATION: u8 = 3;
+const OPT_MTU: u8 = 5;
+
+/// Python wrapper for an NDP option
+/// Represents one type-length-value option of a Neighbor Discovery message
+#[pyclass]
+#[derive(Clone)]
+pub struct NdpOption {
+    option_type: u8,
+    // Option contents after the type and length bytes, including padding
+    data: Vec<u8>,
+}
+
+#[pymethods]
+impl NdpOption {
+    /// Create a new NDP option
+    ///
+    /// The data is zero-padded so the option is a multiple of 8 bytes long.
+    ///
+    /// Args:
+    ///     option_type: Option type (e.g. 1 for source link-layer address)
+    ///     data: Option contents after the type and length bytes
+    ///
+    /// Raises:
+    ///     ValueError: If the option would be longer than 2040 bytes
+    #[new]
+    pub fn new(option_type: u8, data: Vec<u8>) -> PyResult<Self> {
+        let mut data = data;
+        while !(data.len() + 2).is_multiple_of(8) {
+            data.push(0);
+        }
+        if data.len() + 2 > 255 * 8 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                format!("NDP option is {} bytes long, at most 2040 are allowed", data.len() + 2)
+            ));
+        }
+        Ok(Self { option_type, data })
+    }
+
+    /// Create a source link-layer address option
+    ///
+    /// Args:
+    ///     address: 6-byte MAC address
+    #[staticmethod]
+    pub fn source_link_layer_address(address: [u8; 6]) -> Self {
+        Self { option_type: OPT_SOURCE_LINK_LAYER_ADDRESS, data: address.to_vec() }
+    }
+
+    /// Create a target link-layer address option
+    ///
+    /// Args:
+    ///     address: 6-byte MAC address
+    #[staticmethod]
+    pub fn target_link_layer_address(address: [u8; 6]) -> Self {
+        Self { option_type: OPT_TARGET_LINK_LAYER_ADDRESS, data: address.to_vec() }
+    }
+
+    /// Get the option type
+    #[getter]
+    pub fn option_type(&self) -> u8 {
+        self.option_type
+    }
+
+    /// Get the option contents after the type and length bytes
+    #[getter]
+    pub fn data(&self) -> Vec<u8> {
+        self.data.clone()
+    }
+
+    /// Get the link-layer address of source/target link-layer address
+    /// options (None for other options)
+    #[getter]
+    pub fn link_layer_address(&self) -> Option<Vec<u8>> {
+        match self.option_type {
+            OPT_SOURCE_LINK_LAYER_ADDRESS | OPT_TARGET_LINK_LAYER_ADDRESS => {
+                // Ethernet addresses are 6 bytes; the rest is padding
+                Some(self.data[..self.data.len().min(6)].to_vec())
+            },
+            _ => None,
+        }
+    }
+
+    /// Get the MTU of MTU options (None for other options)
+    #[getter]
+    pub fn mtu(&self) -> Option<u32> {
+        if self.option_type == OPT_MTU && self.data.len() >= 6 {
+            Some(u32::from_be_bytes([self.data[2], self.data[3], self.data[4], self.data[5]]))
+        } else {
+            None
+        }
+    }
+
+    /// Get the prefix of prefix information options (None for other options)
+    #[getter]
+    pub fn prefix(&self) -> Option<[u8; 16]> {
+        let info = self.prefix_information()?;
+        let mut prefix = [0u8; 16];
+        prefix.copy_from_slice(&info[14..30]);
+        Some(prefix)
+    }
+
+    /// Get the prefix length of prefix information options
+    #[getter]
+    pub fn prefix_length(&self) -> Option<u8> {
+        Some(self.prefix_information()?[0])
+    }
+
+    /// Get the on-link (L) flag of prefix information options
+    #[getter]
+    pub fn on_link(&self) -> Option<bool> {
+        Some(self.prefix_information()?[1] & 0x80 != 0)
+    }
+
+    /// Get the autonomous address-configuration (A) flag of prefix
+    /// information options
+    #[getter]
+    pub fn autonomous(&self) -> Option<bool> {
+        Some(self.prefix_information()?[1] & 0x40 != 0)
+    }
+
+    /// Get the valid lifetime in seconds of prefix information options
+    #[getter]
+    pub fn valid_lifetime(&self) -> Option<u32> {
+        let info = self.prefix_information()?;
+        Some(u32::from_be_bytes([info[2], info[3], info[4], info[5]]))
+    }
+
+    /// Get the preferred lifetime in seconds of prefix information options
+    #[getter]
+    pub fn preferred_lifetime(&self) -> Option<u32> {
+        let info = self.prefix_information()?;
+        Some(u32::from_be_bytes([info[6], info[7], info[8], info[9]]))
+    }
+
+    /// Serialize the option to bytes
+    ///
+    /// Returns:
+    ///     A vector of bytes representing the option, padded to 8 bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.data.len() + 8);
+        buf.push(self.option_type);
+        buf.push(0); // length, filled in below
+        buf.extend_from_slice(&self.data);
+        while !buf.len().is_multiple_of(8) {
+            buf.push(0);
+        }
+        buf[1] = (buf.len() / 8) as u8;
+        buf
+    }
+}
+
+impl NdpOption {
+    /// Body of a prefix information option, if this is one
+    fn prefix_information(&self) -> Option<&[u8]> {
+        if self.option_type == OPT_PREFIX_INFORMATION && self.data.len() >= 30 {
+            Some(&self.data)
+        } else {
+            None
+        }
+    }
+}
+
+/// Split an NDP options area into options
+///
+/// Parsing stops at an option with a zero length or one that runs past the
+/// end of the area, and the bytes from there on are returned as well.
+fn parse_options(mut slice: &[u8]) -> (Vec<NdpOption>, &[u8]) {
+    let mut options = Vec::new();
+    while slice.len() >= 2 {
+        let len = usize::from(slice[1]) * 8;
+        if len == 0 || len > slice.len() {
+            break;
+        }
+        options.push(NdpOption {
+            option_type: slice[0],
+            data: slice[2..len].to_vec(),
+        });
+        slice = &slice[len..];
+    }
+    (options, slice)
+}
+
+/// Python wrapper for an ICMPv6 header
+/// Represents the first 8 bytes of an ICMPv6 message: type, code, checksum
+/// and the type-specific "rest of header"
+///
+/// For Neighbor Discovery messages (types 133-137) the message body,
+/// including the options, is part of the header; bytes that don't form
+/// valid options end up in the packet payload. For all other messages the
+/// body (echo data or the original datagram of error messages) ends up in
+/// the packet payload.
+#[pyclass]
+#[derive(Clone)]
+pub struct Icmpv6Header {
+    icmp_type: u8,
+    code: u8,
+    checksum: u16,
+    rest_of_header: [u8; 4],
+    // NDP: fixed part of the message body after the first 8 bytes
+    ndp_body: Vec<u8>,
+    // NDP: options following the fixed part
+    options: Vec<NdpOption>,
+    // Copy of the message body of error messages, set when parsing
+    original_datagram: Vec<u8>,
+}
+
+#[pymethods]
+impl Icmpv6Header {
+    /// Create a new ICMPv6 header
+    ///
+    /// Args:
+    ///     icmp_type: ICMPv6 message type (e.g. 128 for echo request)
+    ///     code: ICMPv6 message code (default: 0)
+    ///     rest_of_header: The 4 type-specific bytes after the checksum (default: zeros)
+    ///     checksum: Checksum field (default: 0, see calc_checksum)
+    #[new]
+    #[pyo3(signature = (icmp_type, code = 0, rest_of_header = [0; 4], checksum = 0))]
+    pub fn new(icmp_type: u8, code: u8, rest_of_header: [u8; 4], checksum: u16) -> Self {
+        Self {
+            icmp_type,
+            code,
+            checksum,
+            rest_of_header,
+            ndp_body: Vec::new(),
+            options: Vec::new(),
+            original_datagram: Vec::new(),
+        }
+    }
+
+    /// Create an echo request (ping) header
+    ///
+    /// Args:
+    ///     identifier: Echo identifier
+    ///     sequence: Echo sequence number
+    #[staticmethod]
+    pub fn echo_request(identifier: u16, sequence: u16) -> Self {
+        Self::new(ECHO_REQUEST, 0, id_and_sequence(identifier, sequence), 0)
+    }
+
+    /// Create an echo reply header
+    ///
+    /// Args:
+    ///     identifier: Echo identifier
+    ///     sequence: Echo sequence number
+    #[staticmethod]
+    pub fn echo_reply(identifier: u16, sequence: u16) -> Self {
+        Self::new(ECHO_REPLY, 0, id_and_sequence(identifier, sequence), 0)
+    }
+
+    /// Create a router solicitation message
+    ///
+    /// Args:
+    ///     options: NDP options, usually a source link-layer address (default: none)
+    #[staticmethod]
+    #[pyo3(signature = (options = Vec::new()))]
+    pub fn router_solicitation(options: Vec<NdpOption>) -> Self {
+        Self::ndp(ROUTER_SOLICITATION, [0; 4], Vec::new(), options)
+    }
+
+    /// Create a router advertisement message
+    ///
+    /// Args:
+    ///     cur_hop_limit: Hop limit hosts should use (default: 64)
+    ///     managed: Managed address configuration (M) flag (default: False)
+    ///     other: Other configuration (O) flag (default: False)
+    ///     router_lifetime: Lifetime of the default router in seconds (default: 1800)
+    ///     reachable_time: Reachable time in milliseconds (default: 0)
+    ///     retrans_timer: Retransmission timer in milliseconds (default: 0)
+    ///     options: NDP options such as prefix information (default: none)
+    #[staticmethod]
+    #[pyo3(signature = (cur_hop_limit = 64, managed = false, other = false, router_lifetime = 1800, reachable_time = 0, retrans_timer = 0, options = Vec::new()))]
+    pub fn router_advertisement(
+        cur_hop_limit: u8,
+        managed: bool,
+        other: bool,
+        router_lifetime: u16,
+        reachable_time: u32,
+        retrans_timer: u32,
+        options: Vec<NdpOption>,
+    ) -> Self {
+        let flags = (u8::from(managed) << 7) | (u8::from(other) << 6);
+        let lifetime = router_lifetime.to_be_bytes();
+        let mut body = reachable_time.to_be_bytes().to_vec();
+        body.extend_from_slice(&retrans_timer.to_be_bytes());
+        Self::ndp(ROUTER_ADVERTISEMENT, [cur_hop_limit, flags, lifetime[0], lifetime[1]], body, options)
+    }
+
+    /// Create a neighbor solicitation message
+    ///
+    /// Args:
+    ///     target_address: 16-byte IPv6 address being resolved
+    ///     options: NDP options, usually a source link-layer address (default: none)
+    #[staticmethod]
+    #[pyo3(signature = (target_address, options = Vec::new()))]
+    pub fn neighbor_solicitation(target_address: [u8; 16], options: Vec<NdpOption>) -> Self {
+        Self::ndp(NEIGHBOR_SOLICITATION, [0; 4], target_address.to_vec(), options)
+    }
+
+    /// Create a neighbor advertisement message
+    ///
+    /// Args:
+    ///     target_address: 16-byte IPv6 address being advertised
+    ///     router: Router (R) flag (default: False)
+    ///     solicited: Solicited (S) flag (default: True)
+    ///     override_flag: Override (O) flag (default: True)
+    ///     options: NDP options, usually a target link-layer address (default: none)
+    #[staticmethod]
+    #[pyo3(signature = (target_address, router = false, solicited = true, override_flag = true, options = Vec::new()))]
+    pub fn neighbor_advertisement(
+        target_address: [u8; 16],
+        router: bool,
+        solicited: bool,
+        override_flag: bool,
+        options: Vec<NdpOption>,
+    ) -> Self {
+        let flags = (u8::from(router) << 7) | (u8::from(solicited) << 6) | (u8::from(override_flag) << 5);
+        Self::ndp(NEIGHBOR_ADVERTISEMENT, [flags, 0, 0, 0], target_address.to_vec(), options)
+    }
+
+    /// Get the ICMPv6 message type
+    #[getter]
+    pub fn icmp_type(&self) -> u8 {
+        self.icmp_type
+    }
+
+    /// Get the ICMPv6 message code
+    #[getter]
+    pub fn code(&self) -> u8 {
+        self.code
+    }
+
+    /// Get the checksum field
+    #[getter]
+    pub fn checksum(&self) -> u16 {
+        self.checksum
+    }
+
+    /// Set the checksum field
+    #[setter]
+    pub fn set_checksum(&mut self, checksum: u16) {
+        self.checksum = checksum;
+    }
+
+    /// Get the 4 type-specific bytes after the checksum
+    #[getter]
+    pub fn rest_of_header(&self) -> [u8; 4] {
+        self.rest_of_header
+    }
+
+    /// Get the name of the message type
+    ///
+    /// Returns "destination_unreachable", "packet_too_big",
+    /// "time_exceeded", "parameter_problem", "echo_request", "echo_reply",
+    /// "router_solicitation", "router_advertisement",
+    /// "neighbor_solicitation", "neighbor_advertisement", "redirect" or None
+    /// for other types.
+    #[getter]
+    pub fn type_name(&self) -> Option<&'static str> {
+        match self.icmp_type {
+            DESTINATION_UNREACHABLE => Some("destination_unreachable"),
+            PACKET_TOO_BIG => Some("packet_too_big"),
+            TIME_EXCEEDED => Some("time_exceeded"),
+            PARAMETER_PROBLEM => Some("parameter_problem"),
+            ECHO_REQUEST => Some("echo_request"),
+            ECHO_REPLY => Some("echo_reply"),
+            ROUTER_SOLICITATION => Some("router_solicitation"),
+            ROUTER_ADVERTISEMENT => Some("router_advertisement"),
+            NEIGHBOR_SOLICITATION => Some("neighbor_solicitation"),
+            NEIGHBOR_ADVERTISEMENT => Some("neighbor_advertisement"),
+            REDIRECT => Some("redirect"),
+            _ => None,
+        }
+    }
+
+    /// Get the identifier of echo messages (None otherwise)
+    #[getter]
+    pub fn identifier(&self) -> Option<u16> {
+        if matches!(self.icmp_type, ECHO_REQUEST | ECHO_REPLY) {
+            Some(u16::from_be_bytes([self.rest_of_header[0], self.rest_of_header[1]]))
+        } else {
+            None
+        }
+    }
+
+    /// Get the sequence number of echo messages (None otherwise)
+    #[getter]
+    pub fn sequence(&self) -> Option<u16> {
+        if matches!(self.icmp_type, ECHO_REQUEST | ECHO_REPLY) {
+            Some(u16::from_be_bytes([self.rest_of_header[2], self.rest_of_header[3]]))
+        } else {
+            None
+        }
+    }
+
+    /// Get the MTU of packet too big messages (None otherwise)
+    #[getter]
+    pub fn mtu(&self) -> Option<u32> {
+        if self.icmp_type == PACKET_TOO_BIG {
+            Some(u32::from_be_bytes(self.rest_of_header))
+        } else {
+            None
+        }
+    }
+
+    /// Get the pointer to the offending byte of parameter problem messages
+    /// (None otherwise)
+    #[getter]
+    pub fn pointer(&self) -> Option<u32> {
+        if self.icmp_type == PARAMETER_PROBLEM {
+            Some(u32::from_be_bytes(self.rest_of_header))
+        } else {
+            None
+        }
+    }
+
+    /// Check if this is an error message that quotes an original datagram
+    pub fn is_error(&self) -> bool {
+        matches!(
+            self.icmp_type,
+            DESTINATION_UNREACHABLE | PACKET_TOO_BIG | TIME_EXCEEDED | PARAMETER_PROBLEM
+        )
+    }
+
+    /// Check if this is a Neighbor Discovery message
+    pub fn is_ndp(&self) -> bool {
+        is_ndp_type(self.icmp_type)
+    }
+
+    /// Get the quoted original datagram of error messages
+    ///
+    /// Holds as much of the datagram that caused the error as fit in the
+    /// message. None for other messages and for headers built in Python.
+    #[getter]
+    pub fn original_datagram(&self) -> Option<Vec<u8>> {
+        if self.is_error() && !self.original_datagram.is_empty() {
+            Some(self.original_datagram.clone())
+        } else {
+            None
+        }
+    }
+
+    /// Get the current hop limit of router advertisements (None otherwise)
+    #[getter]
+    pub fn cur_hop_limit(&self) -> Option<u8> {
+        if self.icmp_type == ROUTER_ADVERTISEMENT {
+            Some(self.rest_of_header[0])
+        } else {
+            None
+        }
+    }
+
+    /// Get the managed address configuration (M) flag of router advertisements
+    #[getter]
+    pub fn managed(&self) -> Option<bool> {
+        self.flag(ROUTER_ADVERTISEMENT, 1, 0x80)
+    }
+
+    /// Get the other configuration (O) flag of router advertisements
+    #[getter]
+    pub fn other(&self) -> Option<bool> {
+        self.flag(ROUTER_ADVERTISEMENT, 1, 0x40)
+    }
+
+    /// Get the router lifetime in seconds of router advertisements
+    #[getter]
+    pub fn router_lifetime(&self) -> Option<u16> {
+        if self.icmp_type == ROUTER_ADVERTISEMENT {
+            Some(u16::from_be_bytes([self.rest_of_header[2], self.rest_of_header[3]]))
+        } else {
+            None
+        }
+    }
+
+    /// Get the reachable time in milliseconds of router advertisements
+    #[getter]
+    pub fn reachable_time(&self) -> Option<u32> {
+        if self.icmp_type == ROUTER_ADVERTISEMENT && self.ndp_body.len() >= 8 {
+            let b = &self.ndp_body;
+            Some(u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+        } else {
+            None
+        }
+    }
+
+    /// Get the retransmission timer in milliseconds of router advertisements
+    #[getter]
+    pub fn retrans_timer(&self) -> Option<u32> {
+        if self.icmp_type == ROUTER_ADVERTISEMENT && self.ndp_body.len() >= 8 {
+            let b = &self.ndp_body;
+            Some(u32::from_be_bytes([b[4], b[5], b[6], b[7]]))
+        } else {
+            None
+        }
+    }
+
+    /// Get the router (R) flag of neighbor advertisements
+    #[getter]
+    pub fn router(&self) -> Option<bool> {
+        self.flag(NEIGHBOR_ADVERTISEMENT, 0, 0x80)
+    }
+
+    /// Get the solicited (S) flag of neighbor advertisements
+    #[getter]
+    pub fn solicited(&self) -> Option<bool> {
+        self.flag(NEIGHBOR_ADVERTISEMENT, 0, 0x40)
+    }
+
+    /// Get the override (O) flag of neighbor advertisements
+    #[getter]
+    pub fn override_flag(&self) -> Option<bool> {
+        self.flag(NEIGHBOR_ADVERTISEMENT, 0, 0x20)
+    }
+
+    /// Get the target address of neighbor solicitations/advertisements and
+    /// redirects (None otherwise)
+    #[getter]
+    pub fn target_address(&self) -> Option<[u8; 16]> {
+        match self.icmp_type {
+            NEIGHBOR_SOLICITATION | NEIGHBOR_ADVERTISEMENT | REDIRECT => self.address_at(0),
+            _ => None,
+        }
+    }
+
+    /// Get the destination address of redirects (None otherwise)
+    #[getter]
+    pub fn destination_address(&self) -> Option<[u8; 16]> {
+        if self.icmp_type == REDIRECT {
+            self.address_at(16)
+        } else {
+            None
+        }
+    }
+
+    /// Get the options of Neighbor Discovery messages
+    #[getter]
+    pub fn options(&self) -> Vec<NdpOption> {
+        self.options.clone()
+    }
+
+    /// Set the options of a Neighbor Discovery message
+    #[setter]
+    pub fn set_options(&mut self, options: Vec<NdpOption>) {
+        self.options = options;
+    }
+
+    /// Calculate and set the checksum over the IPv6 pseudo header, the
+    /// header and the message body
+    ///
+    /// Args:
+    ///     source: 16-byte source IPv6 address
+    ///     destination: 16-byte destination IPv6 address
+    ///     payload: Message body following the header (default: empty)
+    ///
+    /// Returns:
+    ///     int: The new checksum
+    #[pyo3(signature = (source, destination, payload = Vec::new()))]
+    pub fn calc_checksum(&mut self, source: [u8; 16], destination: [u8; 16], payload: Vec<u8>) -> u16 {
+        self.checksum = 0;
+        let message = self.to_bytes();
+        let length = ((message.len() + payload.len()) as u32).to_be_bytes();
+        self.checksum = internet_checksum(&[
+            &source,
+            &destination,
+            &length,
+            &[0, 0, 0, etherparse::ip_number::IPV6_ICMP],
+            &message,
+            &payload,
+        ]);
+        self.checksum
+    }
+
+    /// Get the header length in bytes (8, plus the body of NDP messages)
+    #[getter]
+    pub fn header_length(&self) -> usize {
+        8 + self.ndp_body.len() + self.options.iter().map(|o| o.data.len() + 2).sum::<usize>()
+    }
+
+    /// Serialize the ICMPv6 header to bytes
+    ///
+    /// Returns:
+    ///     A vector of bytes representing the header (and NDP body)
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.header_length());
+        buf.push(self.icmp_type);
+        buf.push(self.code);
+        buf.extend_from_slice(&self.checksum.to_be_bytes());
+        buf.extend_from_slice(&self.rest_of_header);
+        buf.extend_from_slice(&self.ndp_body);
+        for option in &self.options {
+            buf.extend(option.to_bytes());
+        }
+        buf
+    }
+}
+
+impl Icmpv6Header {
+    /// Read an ICMPv6 header, returning it and the rest of the message
+    ///
+    /// For Neighbor Discovery messages the rest is empty, unless the options
+    /// are malformed, in which case it holds the bytes from the first bad
+    /// option on.
+    pub(crate) fn from_slice(slice: &[u8]) -> Result<(Self, &[u8]), etherparse::ReadError> {
+        if slice.len() < 8 {
+            return Err(etherparse::ReadError::UnexpectedEndOfSlice(8));
+        }
+        let mut header = Self::new(
+            slice[0],
+            slice[1],
+            [slice[4], slice[5], slice[6], slice[7]],
+            u16::from_be_bytes([slice[2], slice[3]]),
+        );
+        let body = &slice[8..];
+        if header.is_ndp() {
+            let fixed = ndp_fixed_len(header.icmp_type);
+            if body.len() < fixed {
+                return Err(etherparse::ReadError::UnexpectedEndOfSlice(8 + fixed));
+            }
+            header.ndp_body = body[..fixed].to_vec();
+            let (options, rest) = parse_options(&body[fixed..]);
+            header.options = options;
+            return Ok((header, rest));
+        }
+        if header.is_error() {
+            header.original_datagram = body.to_vec();
+        }
+        Ok((header, body))
+    }
+
+    /// Create a Neighbor Discovery message
+    fn ndp(icmp_type: u8, rest_of_header: [u8; 4], body: Vec<u8>, options: Vec<NdpOption>) -> Self {
+        let mut header = Self::new(icmp_type, 0, rest_of_header, 0);
+        header.ndp_body = body;
+        header.options = options;
+        header
+    }
+
+    /// Read a flag bit of the rest of the header of the given message type
+    fn flag(&self, icmp_type: u8, index: usize, mask: u8) -> Option<bool> {
+        if self.icmp_type == icmp_type {
+            Some(self.rest_of_header[index] & mask != 0)
+        } else {
+            None
+        }
+    }
+
+    /// Read an IPv6 address from the NDP body
+    fn address_at(&self, offset: usize) -> Option<[u8; 16]> {
+        let bytes = self.ndp_body.get(offset..offset + 16)?;
+        let mut address = [0u8; 16];
+        address.copy_from_slice(bytes);
+        Some(address)
+    }
+}
+
+fn is_ndp_type(icmp_type: u8) -> bool {
+    (ROUTER_SOLICITATION..=REDIRECT).contains(&icmp_type)
+}
+
+/// Length of the fixed NDP message body between the first 8 bytes and the options
+fn ndp_fixed_len(icmp_type: u8) -> usize {
+    match icmp_type {
+        ROUTER_ADVERTISEMENT => 8,
+        NEIGHBOR_SOLICITATION | NEIGHBOR_ADVERTISEMENT => 16,
+        REDIRECT => 32,
+        _ => 0,
+    }
+}
@@ -1,4 +1,6 @@
 use pyo3::prelude::*;
+use crate::headers::field_value;
+use crate::headers::ipv4_options::{decode_options, encode_options, Ipv4Option};
 
 /// Python wrapper for etherparse::Ipv4Header
 /// Represents an IPv4 header
@@ -19,9 +21,19 @@ impl Ipv4Header {
     ///     destination: 4-byte IPv4 destination address
     ///     ttl: Time to live value (default: 64)
     ///     protocol: Protocol identifier (e.g., 6 for TCP) (default: 0)
+    ///     options: List of Ipv4Option* objects (default: none)
+    ///
+    /// Raises:
+    ///     ValueError: If the options don't fit into 40 bytes
     #[new]
-    #[pyo3(signature = (source, destination, ttl=64, protocol=0))]
-    pub fn new(source: [u8; 4], destination: [u8; 4], ttl: u8, protocol: u8) -> Self {
+    #[pyo3(signature = (source, destination, ttl=64, protocol=0, options=Vec::new()))]
+    pub fn new(
+        source: [u8; 4],
+        destination: [u8; 4],
+        ttl: u8,
+        protocol: u8,
+        options: Vec<Ipv4Option>,
+    ) -> PyResult<Self> {
         // Start with a default IPv4 header
         let mut header = etherparse::Ipv4Header::default();
         
@@ -32,7 +44,9 @@ impl Ipv4Header {
         header.protocol = protocol;
         
         // Return the wrapped header
-        Self { inner: header }
+        let mut header = Self { inner: header };
+        header.set_options(options)?;
+        Ok(header)
     }
 
     /// Get the source IPv4 address
@@ -88,20 +102,219 @@ impl Ipv4Header {
     pub fn fragment_offset(&self) -> u16 {
         self.inner.fragments_offset
     }
+    
+    /// Get the Differentiated Services Code Point (6 bits)
+    #[getter]
+    pub fn dscp(&self) -> u8 {
+        self.inner.differentiated_services_code_point
+    }
+    
+    /// Get the Explicit Congestion Notification bits (2 bits)
+    #[getter]
+    pub fn ecn(&self) -> u8 {
+        self.inner.explicit_congestion_notification
+    }
+    
+    /// Get the length of the payload after the header in bytes
+    #[getter]
+    pub fn payload_length(&self) -> u16 {
+        self.inner.payload_len
+    }
+    
+    /// Get the total length field (header plus payload)
+    #[getter]
+    pub fn total_length(&self) -> u16 {
+        self.inner.total_len()
+    }
+    
+    /// Get the header checksum field
+    #[getter]
+    pub fn checksum(&self) -> u16 {
+        self.inner.header_checksum
+    }
+    
+    /// Check if the header checksum field matches the other fields
+    #[getter]
+    pub fn checksum_valid(&self) -> bool {
+        self.inner
+            .calc_header_checksum()
+            .is_ok_and(|checksum| checksum == self.inner.header_checksum)
+    }
+    
+    /// Calculate and set the header checksum
+    ///
+    /// Returns:
+    ///     int: The new checksum
+    pub fn calc_checksum(&mut self) -> u16 {
+        // The setters keep all fields in range, so this can't fail
+        self.inner.header_checksum = self.inner.calc_header_checksum().unwrap();
+        self.inner.header_checksum
+    }
+    
+    // Field setters
+    
+    /// Set the source IPv4 address
+    #[setter]
+    pub fn set_source(&mut self, source: [u8; 4]) {
+        self.inner.source = source;
+    }
+    
+    /// Set the destination IPv4 address
+    #[setter]
+    pub fn set_destination(&mut self, destination: [u8; 4]) {
+        self.inner.destination = destination;
+    }
+    
+    /// Set the Time to Live (TTL) value
+    ///
+    /// Raises:
+    ///     ValueError: If the value is out of range (0-255)
+    #[setter]
+    pub fn set_ttl(&mut self, ttl: i64) -> PyResult<()> {
+        self.inner.time_to_live = field_value("TTL", ttl, u8::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Set the protocol identifier
+    ///
+    /// Raises:
+    ///     ValueError: If the value is out of range (0-255)
+    #[setter]
+    pub fn set_protocol(&mut self, protocol: i64) -> PyResult<()> {
+        self.inner.protocol = field_value("Protocol", protocol, u8::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Set the identification field
+    ///
+    /// Raises:
+    ///     ValueError: If the value is out of range (0-65535)
+    #[setter]
+    pub fn set_identification(&mut self, identification: i64) -> PyResult<()> {
+        self.inner.identification = field_value("Identification", identification, u16::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Set the "Don't Fragment" flag
+    #[setter]
+    pub fn set_dont_fragment(&mut self, value: bool) {
+        self.inner.dont_fragment = value;
+    }
+    
+    /// Set the "More Fragments" flag
+    #[setter]
+    pub fn set_more_fragments(&mut self, value: bool) {
+        self.inner.more_fragments = value;
+    }
+    
+    /// Set the fragment offset (in 8-byte units)
+    ///
+    /// Raises:
+    ///     ValueError: If the offset is out of range (0-8191)
+    #[setter]
+    pub fn set_fragment_offset(&mut self, offset: i64) -> PyResult<()> {
+        self.inner.fragments_offset = field_value("Fragment offset", offset, 0x1fff)?;
+        Ok(())
+    }
+    
+    /// Set the Differentiated Services Code Point
+    ///
+    /// Raises:
+    ///     ValueError: If the value is out of range (0-63)
+    #[setter]
+    pub fn set_dscp(&mut self, dscp: i64) -> PyResult<()> {
+        self.inner.differentiated_services_code_point = field_value("DSCP", dscp, 0x3f)?;
+        Ok(())
+    }
+    
+    /// Set the Explicit Congestion Notification bits
+    ///
+    /// Raises:
+    ///     ValueError: If the value is out of range (0-3)
+    #[setter]
+    pub fn set_ecn(&mut self, ecn: i64) -> PyResult<()> {
+        self.inner.explicit_congestion_notification = field_value("ECN", ecn, 3)?;
+        Ok(())
+    }
+    
+    /// Set the length of the payload after the header in bytes
+    ///
+    /// The total length field becomes the header length plus this value.
+    ///
+    /// Raises:
+    ///     ValueError: If the total length would exceed 65535 bytes
+    #[setter]
+    pub fn set_payload_length(&mut self, length: i64) -> PyResult<()> {
+        let max = u16::MAX - self.inner.header_len() as u16;
+        self.inner.payload_len = field_value("Payload length", length, max.into())?;
+        Ok(())
+    }
+    
+    /// Set the header checksum field
+    ///
+    /// Raises:
+    ///     ValueError: If the checksum doesn't fit into 16 bits
+    #[setter]
+    pub fn set_checksum(&mut self, checksum: i64) -> PyResult<()> {
+        self.inner.header_checksum = field_value("Checksum", checksum, u16::MAX.into())?;
+        Ok(())
+    }
+
+    /// Get the options as Ipv4Option* objects
+    ///
+    /// Record Route, Source Route, Timestamp, Router Alert and Security
+    /// options are decoded; any other option is returned as an
+    /// Ipv4OptionRaw. No-operation padding is left out.
+    #[getter]
+    pub fn options(&self) -> Vec<Ipv4Option> {
+        decode_options(self.inner.options())
+    }
+    
+    /// Replace the options
+    ///
+    /// The options area is padded to a multiple of 4 bytes and the header
+    /// length grows or shrinks to match.
+    ///
+    /// Raises:
+    ///     ValueError: If the options don't fit into 40 bytes
+    #[setter]
+    pub fn set_options(&mut self, options: Vec<Ipv4Option>) -> PyResult<()> {
+        let area = encode_options(&options)?;
+        if 20 + area.len() + usize::from(self.inner.payload_len) > usize::from(u16::MAX) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "IPv4 options would make the total length exceed 65535 bytes",
+            ));
+        }
+        self.inner.set_options(&area).map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Invalid IPv4 options: {}", e))
+        })
+    }
+    
+    /// Get the raw bytes of the options area, including padding
+    #[getter]
+    pub fn options_bytes(&self) -> Vec<u8> {
+        self.inner.options().to_vec()
+    }
 
     /// Serialize the IPv4 header to bytes
+    ///
+    /// Args:
+    ///     recompute_checksum: Calculate the header checksum from the other
+    ///         fields instead of writing the checksum field (default: True)
     /// 
     /// Returns:
     ///     A vector of bytes representing the header
-    pub fn to_bytes(&self) -> Vec<u8> {
-        // Get the size of the header
-        let size = self.inner.header_len() as usize;
-        
-        // Create a buffer with the right size
-        let mut buf = vec![0u8; size];
+    #[pyo3(signature = (recompute_checksum = true))]
+    pub fn to_bytes(&self, recompute_checksum: bool) -> Vec<u8> {
+        // Create a buffer with room for the header and its options
+        let mut buf = Vec::with_capacity(self.inner.header_len());
         
-        // Use etherparse's own serialization
-        self.inner.write(&mut buf).unwrap();
+        // Use etherparse's own serialization, which appends to the buffer
+        if recompute_checksum {
+            self.inner.write(&mut buf).unwrap();
+        } else {
+            self.inner.write_raw(&mut buf).unwrap();
+        }
         
         // Return the buffer
         buf
@@ -0,0 +1,327 @@
+use pyo3::prelude::*;
+use crate::headers::{decode_option_area, encode_option_area};
+
+// IPv4 option types (copy flag, class and number combined)
+const RECORD_ROUTE: u8 = 7;
+const TIMESTAMP: u8 = 68;
+const SECURITY: u8 = 130;
+const LOOSE_SOURCE_ROUTE: u8 = 131;
+const STRICT_SOURCE_ROUTE: u8 = 137;
+const ROUTER_ALERT: u8 = 148;
+
+/// IPv4 Record Route option (type 7)
+#[pyclass]
+#[derive(Clone)]
+pub struct Ipv4OptionRecordRoute {
+    /// Offset of the next free slot, counted from the option start
+    #[pyo3(get, set)]
+    pub pointer: u8,
+
+    /// All address slots, used and free
+    #[pyo3(get, set)]
+    pub routes: Vec<[u8; 4]>,
+}
+
+#[pymethods]
+impl Ipv4OptionRecordRoute {
+    /// Create a new option
+    ///
+    /// Args:
+    ///     routes: Address slots, filled in by routers up to the pointer
+    ///     pointer: Offset of the next free slot, counted from the option start
+    ///         (default: 4, no slot used yet)
+    #[new]
+    #[pyo3(signature = (routes, pointer = 4))]
+    pub fn new(routes: Vec<[u8; 4]>, pointer: u8) -> Self {
+        Self { pointer, routes }
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        route_option(RECORD_ROUTE, self.pointer, &self.routes)
+    }
+}
+
+/// IPv4 Loose (type 131) or Strict (type 137) Source Route option
+#[pyclass]
+#[derive(Clone)]
+pub struct Ipv4OptionSourceRoute {
+    /// Offset of the next address to use, counted from the option start
+    #[pyo3(get, set)]
+    pub pointer: u8,
+
+    /// Route addresses
+    #[pyo3(get, set)]
+    pub routes: Vec<[u8; 4]>,
+
+    /// Whether this is a strict (True) or loose (False) source route
+    #[pyo3(get, set)]
+    pub strict: bool,
+}
+
+#[pymethods]
+impl Ipv4OptionSourceRoute {
+    /// Create a new option
+    ///
+    /// Args:
+    ///     routes: Addresses the packet has to pass through
+    ///     pointer: Offset of the next address to use, counted from the option
+    ///         start (default: 4)
+    ///     strict: Strict instead of loose source routing (default: False)
+    #[new]
+    #[pyo3(signature = (routes, pointer = 4, strict = false))]
+    pub fn new(routes: Vec<[u8; 4]>, pointer: u8, strict: bool) -> Self {
+        Self { pointer, routes, strict }
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let option_type = if self.strict { STRICT_SOURCE_ROUTE } else { LOOSE_SOURCE_ROUTE };
+        route_option(option_type, self.pointer, &self.routes)
+    }
+}
+
+/// IPv4 Timestamp option (type 68)
+///
+/// With flag 0 only timestamps are recorded; with flags 1 and 3 each
+/// timestamp is preceded by an address, given in addresses.
+#[pyclass]
+#[derive(Clone)]
+pub struct Ipv4OptionTimestamp {
+    /// Offset of the next free slot, counted from the option start
+    #[pyo3(get, set)]
+    pub pointer: u8,
+
+    /// Number of hosts that couldn't register a timestamp (4 bits)
+    #[pyo3(get, set)]
+    pub overflow: u8,
+
+    /// Timestamp format flag (4 bits)
+    #[pyo3(get, set)]
+    pub flag: u8,
+
+    /// Timestamp slots
+    #[pyo3(get, set)]
+    pub timestamps: Vec<u32>,
+
+    /// Address slots (flags 1 and 3 only)
+    #[pyo3(get, set)]
+    pub addresses: Vec<[u8; 4]>,
+}
+
+#[pymethods]
+impl Ipv4OptionTimestamp {
+    /// Create a new option
+    ///
+    /// Args:
+    ///     timestamps: Timestamp slots in milliseconds since midnight UT
+    ///     addresses: Address slots for flags 1 and 3 (default: none)
+    ///     flag: 0 = timestamps only, 1 = address and timestamp, 3 =
+    ///         prespecified addresses (default: 0)
+    ///     overflow: Number of hosts that couldn't register (default: 0)
+    ///     pointer: Offset of the next free slot, counted from the option start
+    ///         (default: 5)
+    #[new]
+    #[pyo3(signature = (timestamps, addresses = Vec::new(), flag = 0, overflow = 0, pointer = 5))]
+    pub fn new(timestamps: Vec<u32>, addresses: Vec<[u8; 4]>, flag: u8, overflow: u8, pointer: u8) -> Self {
+        Self { pointer, overflow, flag, timestamps, addresses }
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![TIMESTAMP, 0, self.pointer, (self.overflow << 4) | (self.flag & 0x0f)];
+        for (i, timestamp) in self.timestamps.iter().enumerate() {
+            if self.flag != 0 {
+                buf.extend_from_slice(&self.addresses.get(i).copied().unwrap_or_default());
+            }
+            buf.extend_from_slice(&timestamp.to_be_bytes());
+        }
+        buf[1] = buf.len() as u8;
+        buf
+    }
+}
+
+/// IPv4 Router Alert option (type 148, RFC 2113)
+#[pyclass]
+#[derive(Clone)]
+pub struct Ipv4OptionRouterAlert {
+    /// Alert value
+    #[pyo3(get, set)]
+    pub value: u16,
+}
+
+#[pymethods]
+impl Ipv4OptionRouterAlert {
+    /// Create a new option
+    ///
+    /// Args:
+    ///     value: Alert value (default: 0, "router shall examine packet")
+    #[new]
+    #[pyo3(signature = (value = 0))]
+    pub fn new(value: u16) -> Self {
+        Self { value }
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let value = self.value.to_be_bytes();
+        vec![ROUTER_ALERT, 4, value[0], value[1]]
+    }
+}
+
+/// IPv4 Basic Security option (type 130, RFC 1108)
+#[pyclass]
+#[derive(Clone)]
+pub struct Ipv4OptionSecurity {
+    /// Classification level byte
+    #[pyo3(get, set)]
+    pub classification_level: u8,
+
+    /// Protection authority flag bytes
+    #[pyo3(get, set)]
+    pub protection_authority: Vec<u8>,
+}
+
+#[pymethods]
+impl Ipv4OptionSecurity {
+    /// Create a new option
+    ///
+    /// Args:
+    ///     classification_level: Classification level byte
+    ///     protection_authority: Protection authority flag bytes (default: none)
+    #[new]
+    #[pyo3(signature = (classification_level, protection_authority = Vec::new()))]
+    pub fn new(classification_level: u8, protection_authority: Vec<u8>) -> Self {
+        Self { classification_level, protection_authority }
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![SECURITY, 0, self.classification_level];
+        buf.extend_from_slice(&self.protection_authority);
+        buf[1] = buf.len() as u8;
+        buf
+    }
+}
+
+/// Any other IPv4 option, kept as raw bytes
+#[pyclass]
+#[derive(Clone)]
+pub struct Ipv4OptionRaw {
+    /// Option type byte
+    #[pyo3(get, set)]
+    pub option_type: u8,
+
+    /// Option contents after the type and length bytes
+    #[pyo3(get, set)]
+    pub data: Vec<u8>,
+}
+
+#[pymethods]
+impl Ipv4OptionRaw {
+    /// Create a new option
+    ///
+    /// Args:
+    ///     option_type: Option type byte
+    ///     data: Option contents after the type and length bytes
+    #[new]
+    #[pyo3(signature = (option_type, data = Vec::new()))]
+    pub fn new(option_type: u8, data: Vec<u8>) -> Self {
+        Self { option_type, data }
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![self.option_type, (self.data.len() + 2) as u8];
+        buf.extend_from_slice(&self.data);
+        buf
+    }
+}
+
+/// One decoded IPv4 option, converted to and from the option classes
+#[derive(Clone, FromPyObject, IntoPyObject)]
+pub enum Ipv4Option {
+    RecordRoute(Ipv4OptionRecordRoute),
+    SourceRoute(Ipv4OptionSourceRoute),
+    Timestamp(Ipv4OptionTimestamp),
+    RouterAlert(Ipv4OptionRouterAlert),
+    Security(Ipv4OptionSecurity),
+    Raw(Ipv4OptionRaw),
+}
+
+impl Ipv4Option {
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::RecordRoute(option) => option.to_bytes(),
+            Self::SourceRoute(option) => option.to_bytes(),
+            Self::Timestamp(option) => option.to_bytes(),
+            Self::RouterAlert(option) => option.to_bytes(),
+            Self::Security(option) => option.to_bytes(),
+            Self::Raw(option) => option.to_bytes(),
+        }
+    }
+}
+
+/// Decode an IPv4 options area
+pub(crate) fn decode_options(area: &[u8]) -> Vec<Ipv4Option> {
+    decode_option_area(area, decode_option, |option_type, data| Ipv4Option::Raw(Ipv4OptionRaw::new(option_type, data)))
+}
+
+/// Decode one option from its type and the bytes after the length byte
+fn decode_option(option_type: u8, data: &[u8]) -> Ipv4Option {
+    match (option_type, data) {
+        (RECORD_ROUTE, [pointer, routes @ ..]) => {
+            Ipv4Option::RecordRoute(Ipv4OptionRecordRoute::new(addresses(routes), *pointer))
+        },
+        (LOOSE_SOURCE_ROUTE | STRICT_SOURCE_ROUTE, [pointer, routes @ ..]) => {
+            Ipv4Option::SourceRoute(Ipv4OptionSourceRoute::new(
+                addresses(routes),
+                *pointer,
+                option_type == STRICT_SOURCE_ROUTE,
+            ))
+        },
+        (TIMESTAMP, [pointer, flags, entries @ ..]) => {
+            let flag = flags & 0x0f;
+            let entry_len = if flag == 0 { 4 } else { 8 };
+            let mut timestamps = Vec::new();
+            let mut addresses = Vec::new();
+            for entry in entries.chunks_exact(entry_len) {
+                let (address, timestamp) = entry.split_at(entry_len - 4);
+                if let Ok(address) = <[u8; 4]>::try_from(address) {
+                    addresses.push(address);
+                }
+                timestamps.push(u32::from_be_bytes([timestamp[0], timestamp[1], timestamp[2], timestamp[3]]));
+            }
+            Ipv4Option::Timestamp(Ipv4OptionTimestamp::new(timestamps, addresses, flag, flags >> 4, *pointer))
+        },
+        (ROUTER_ALERT, [high, low]) => {
+            Ipv4Option::RouterAlert(Ipv4OptionRouterAlert::new(u16::from_be_bytes([*high, *low])))
+        },
+        (SECURITY, [classification_level, protection_authority @ ..]) => {
+            Ipv4Option::Security(Ipv4OptionSecurity::new(*classification_level, protection_authority.to_vec()))
+        },
+        _ => Ipv4Option::Raw(Ipv4OptionRaw::new(option_type, data.to_vec())),
+    }
+}
+
+/// Encode options into an options area padded to a multiple of 4 bytes
+pub(crate) fn encode_options(options: &[Ipv4Option]) -> PyResult<Vec<u8>> {
+    encode_option_area("IPv4", options.iter().map(Ipv4Option::to_bytes))
+}
+
+/// Serialize a route option (Record Route or Source Route)
+fn route_option(option_type: u8, pointer: u8, routes: &[[u8; 4]]) -> Vec<u8> {
+    let mut buf = vec![option_type, (3 + 4 * routes.len()) as u8, pointer];
+    for route in routes {
+        buf.extend_from_slice(route);
+    }
+    buf
+}
+
+/// Split a byte string into 4-byte addresses, ignoring a partial last one
+fn addresses(bytes: &[u8]) -> Vec<[u8; 4]> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| [chunk[0], chunk[1], chunk[2], chunk[3]])
+        .collect()
+}
@@ -1,12 +1,19 @@
 use pyo3::prelude::*;
+use crate::headers::field_value;
+use crate::headers::ipv6_extensions::{chain_extensions, CapturedExtension, Ipv6Extension, Ipv6FragmentHeader};
 
 /// Python wrapper for etherparse::Ipv6Header
-/// Represents an IPv6 header
+/// Represents an IPv6 header together with its extension headers
 #[pyclass]
 #[derive(Clone)]
 pub struct Ipv6Header {
     // Store the inner etherparse header
     inner: etherparse::Ipv6Header,
+    // Extension headers in wire order, chained through their next headers
+    extensions: Vec<Ipv6Extension>,
+    // Serialized extension headers: as captured for parsed headers, so
+    // padding and lengths match the wire, and re-encoded when replaced
+    encoded: Vec<Vec<u8>>,
 }
 
 #[pymethods]
@@ -17,30 +24,39 @@ impl Ipv6Header {
     ///     source: 16-byte IPv6 source address
     ///     destination: 16-byte IPv6 destination address
     ///     hop_limit: Hop limit value (default: 64)
-    ///     next_header: Next header identifier (default: 0)
+    ///     next_header: Protocol of the payload after any extension headers (default: 0)
+    ///     flow_label: 20-bit flow label (default: 0)
+    ///     extensions: List of Ipv6* extension header objects in wire order
+    ///         (default: none)
+    ///
+    /// Raises:
+    ///     ValueError: If an extension header can't be serialized or a
+    ///         Hop-by-Hop Options header isn't the first one
     #[new]
-    #[pyo3(signature=(source, destination, hop_limit=64, next_header=0, flow_label=0))]
+    #[pyo3(signature=(source, destination, hop_limit=64, next_header=0, flow_label=0, extensions=Vec::new()))]
     pub fn new(
         source: [u8; 16], 
         destination: [u8; 16], 
         hop_limit: u8, 
         next_header: u8,
-        flow_label: u32
-    ) -> Self {
-        // Start with a default IPv6 header
-        let mut header = etherparse::Ipv6Header::default();
+        flow_label: u32,
+        extensions: Vec<Ipv6Extension>,
+    ) -> PyResult<Self> {
+        // Start with a default IPv6 header and set the user-provided values
+        let header = etherparse::Ipv6Header {
+            source,
+            destination,
+            hop_limit,
+            next_header,
+            // Flow label is only 20 bits, ensure it's masked
+            flow_label: flow_label & 0xFFFFF,
+            ..Default::default()
+        };
         
-        // Set the user-provided values
-        header.source = source;
-        header.destination = destination;
-        header.hop_limit = hop_limit;
-        header.next_header = next_header;
-        
-        // Flow label is only 20 bits, ensure it's masked
-        header.flow_label = flow_label & 0xFFFFF;
-        
-        // Return the wrapped header
-        Self { inner: header }
+        // Return the wrapped header with its extensions chained in
+        let mut header = Self::from(header);
+        header.set_extensions(extensions)?;
+        Ok(header)
     }
 
     /// Get the source IPv6 address
@@ -62,11 +78,47 @@ impl Ipv6Header {
     }
     
     /// Get the next header field (protocol identifier)
+    ///
+    /// With extension headers this is the type of the first one; see
+    /// upper_layer_protocol for the protocol of the payload.
     #[getter]
     pub fn next_header(&self) -> u8 { 
         self.inner.next_header 
     }
     
+    /// Get the protocol of the payload after all extension headers
+    #[getter]
+    pub fn upper_layer_protocol(&self) -> u8 {
+        self.extensions
+            .last()
+            .map_or(self.inner.next_header, Ipv6Extension::next_header)
+    }
+
+    /// Get the extension headers in wire order
+    ///
+    /// Returns Ipv6HopByHopOptions, Ipv6RoutingHeader, Ipv6FragmentHeader,
+    /// Ipv6DestinationOptions and Ipv6AuthenticationHeader objects.
+    #[getter]
+    pub fn extensions(&self) -> Vec<Ipv6Extension> {
+        self.extensions.clone()
+    }
+
+    /// Replace the extension headers
+    ///
+    /// The next header fields are rewritten so the chain leads from this
+    /// header through the extensions to the upper layer protocol.
+    ///
+    /// Raises:
+    ///     ValueError: If an extension header can't be serialized or a
+    ///         Hop-by-Hop Options header isn't the first one
+    #[setter]
+    pub fn set_extensions(&mut self, mut extensions: Vec<Ipv6Extension>) -> PyResult<()> {
+        let upper_layer_protocol = self.upper_layer_protocol();
+        (self.inner.next_header, self.encoded) = chain_extensions(&mut extensions, upper_layer_protocol)?;
+        self.extensions = extensions;
+        Ok(())
+    }
+
     /// Get the traffic class
     #[getter]
     pub fn traffic_class(&self) -> u8 {
@@ -85,34 +137,196 @@ impl Ipv6Header {
         self.inner.payload_length
     }
     
-    /// Get the total header length (fixed for IPv6 base header)
+    // Field setters
+    
+    /// Set the source IPv6 address
+    #[setter]
+    pub fn set_source(&mut self, source: [u8; 16]) {
+        self.inner.source = source;
+    }
+    
+    /// Set the destination IPv6 address
+    #[setter]
+    pub fn set_destination(&mut self, destination: [u8; 16]) {
+        self.inner.destination = destination;
+    }
+    
+    /// Set the hop limit value
+    ///
+    /// Raises:
+    ///     ValueError: If the value is out of range (0-255)
+    #[setter]
+    pub fn set_hop_limit(&mut self, hop_limit: i64) -> PyResult<()> {
+        self.inner.hop_limit = field_value("Hop limit", hop_limit, u8::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Set the next header field
+    ///
+    /// Only the field itself is changed. With extension headers, set
+    /// upper_layer_protocol to change the protocol of the payload instead.
+    ///
+    /// Raises:
+    ///     ValueError: If the value is out of range (0-255)
+    #[setter]
+    pub fn set_next_header(&mut self, next_header: i64) -> PyResult<()> {
+        self.inner.next_header = field_value("Next header", next_header, u8::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Set the protocol of the payload after all extension headers
+    ///
+    /// Raises:
+    ///     ValueError: If the value is out of range (0-255)
+    #[setter]
+    pub fn set_upper_layer_protocol(&mut self, protocol: i64) -> PyResult<()> {
+        let protocol = field_value("Upper layer protocol", protocol, u8::MAX.into())?;
+        self.set_next_header_of(self.extensions.len().checked_sub(1), protocol);
+        Ok(())
+    }
+    
+    /// Set the traffic class
+    ///
+    /// Raises:
+    ///     ValueError: If the value is out of range (0-255)
+    #[setter]
+    pub fn set_traffic_class(&mut self, traffic_class: i64) -> PyResult<()> {
+        self.inner.traffic_class = field_value("Traffic class", traffic_class, u8::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Set the flow label
+    ///
+    /// Raises:
+    ///     ValueError: If the value doesn't fit into 20 bits
+    #[setter]
+    pub fn set_flow_label(&mut self, flow_label: i64) -> PyResult<()> {
+        self.inner.flow_label = field_value("Flow label", flow_label, 0xfffff)?;
+        Ok(())
+    }
+    
+    /// Set the payload length field (extension headers count as payload)
+    ///
+    /// Raises:
+    ///     ValueError: If the value is out of range (0-65535)
+    #[setter]
+    pub fn set_payload_length(&mut self, length: i64) -> PyResult<()> {
+        self.inner.payload_length = field_value("Payload length", length, u16::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Get the header length including extension headers
+    ///
+    /// Parsed extension headers count as captured, including their
+    /// padding, until the extensions are replaced.
     #[getter]
-    pub fn header_length(&self) -> u8 {
+    pub fn header_length(&self) -> usize {
         // IPv6 base header is always 40 bytes
-        40
+        40 + self.encoded.iter().map(Vec::len).sum::<usize>()
     }
 
-    /// Serialize the IPv6 header to bytes
+    /// Serialize the IPv6 header and its extension headers to bytes
     /// 
     /// Returns:
     ///     A vector of bytes representing the header
     pub fn to_bytes(&self) -> Vec<u8> {
-        // IPv6 header is always 40 bytes
-        let mut buf = vec![0u8; 40];
+        // IPv6 header is always 40 bytes, followed by the extensions
+        let mut buf = Vec::with_capacity(40);
         
-        // Use etherparse's own serialization
+        // Use etherparse's own serialization, which appends to the buffer
         self.inner.write(&mut buf).unwrap();
+        for extension in &self.encoded {
+            buf.extend_from_slice(extension);
+        }
         
         // Return the buffer
         buf
     }
 }
 
+impl Ipv6Header {
+    /// Create a header from a parsed base header and its extension chain,
+    /// given with the bytes of each extension as captured
+    pub(crate) fn with_extensions(header: etherparse::Ipv6Header, extensions: Vec<CapturedExtension>) -> Self {
+        let (extensions, encoded) = extensions.into_iter().unzip();
+        Self { inner: header, extensions, encoded }
+    }
+
+    /// Check if the extensions hold a fragment rather than a whole packet
+    pub(crate) fn is_fragmenting_payload(&self) -> bool {
+        self.fragment_header().is_some()
+    }
+
+    /// Get the Fragment header if the packet is a fragment
+    pub(crate) fn fragment_header(&self) -> Option<&Ipv6FragmentHeader> {
+        self.extensions.iter().find_map(|extension| match extension {
+            Ipv6Extension::Fragment(fragment) if fragment.is_fragmenting_payload() => Some(fragment),
+            _ => None,
+        })
+    }
+
+    /// Remove the Fragment headers, relinking the extension chain
+    ///
+    /// The other extensions keep their bytes as captured.
+    pub(crate) fn remove_fragment_header(&mut self) {
+        while let Some(index) = self.extensions.iter().position(|extension| matches!(extension, Ipv6Extension::Fragment(_))) {
+            let next_header = self.extensions.remove(index).next_header();
+            self.encoded.remove(index);
+            self.set_next_header_of(index.checked_sub(1), next_header);
+        }
+    }
+
+    /// Set the next header field of the extension at the given index, or
+    /// of the base header for None
+    fn set_next_header_of(&mut self, index: Option<usize>, next_header: u8) {
+        match index {
+            Some(index) => {
+                self.extensions[index].set_next_header(next_header);
+                // The next header is the first byte of every extension header
+                self.encoded[index][0] = next_header;
+            },
+            None => self.inner.next_header = next_header,
+        }
+    }
+
+    /// Get the destination the packet is finally delivered to
+    ///
+    /// With a routing header that still has segments left this is the last
+    /// segment rather than the destination field (RFC 8200, section 8.1).
+    pub(crate) fn final_destination(&self) -> [u8; 16] {
+        for extension in &self.extensions {
+            let Ipv6Extension::Routing(routing) = extension else {
+                continue;
+            };
+            if routing.segments_left == 0 {
+                break;
+            }
+            let last = match routing.routing_type {
+                // Segment Routing Header: the final segment comes first
+                4 => routing.segments().and_then(|segments| segments.first().copied()),
+                // Type 0 routing header: the final address comes last
+                0 => routing.data.get(4..).and_then(|addresses| {
+                    addresses.chunks_exact(16).last().map(|chunk| {
+                        let mut address = [0u8; 16];
+                        address.copy_from_slice(chunk);
+                        address
+                    })
+                }),
+                _ => None,
+            };
+            if let Some(address) = last {
+                return address;
+            }
+        }
+        self.inner.destination
+    }
+}
+
 /// Convert from etherparse::Ipv6Header to our Ipv6Header
 /// This is used when parsing packets
 impl From<etherparse::Ipv6Header> for Ipv6Header {
     fn from(header: etherparse::Ipv6Header) -> Self {
-        Self { inner: header }
+        Self { inner: header, extensions: Vec::new(), encoded: Vec::new() }
     }
 }
 
@@ -0,0 +1,571 @@
+use pyo3::prelude::*;
+use etherparse::ReadError;
+
+// IP protocol numbers of the IPv6 extension headers
+const HOP_BY_HOP: u8 = 0;
+const ROUTING: u8 = 43;
+const FRAGMENT: u8 = 44;
+const AUTHENTICATION: u8 = 51;
+const DESTINATION_OPTIONS: u8 = 60;
+
+/// "No Next Header", the default next header of a lone extension header
+const NO_NEXT_HEADER: u8 = 59;
+
+// Padding options of Hop-by-Hop and Destination Options headers
+const PAD1: u8 = 0;
+const PADN: u8 = 1;
+
+/// Routing type of the Segment Routing Header (RFC 8754)
+const SEGMENT_ROUTING: u8 = 4;
+
+/// Largest extension header in bytes (header length field of 255)
+const MAX_EXTENSION_LEN: usize = 2048;
+
+/// IPv6 Hop-by-Hop Options header (next header 0)
+///
+/// Options are (type, data) tuples; Pad1 and PadN padding is left out when
+/// decoding and added when serializing.
+#[pyclass]
+#[derive(Clone)]
+pub struct Ipv6HopByHopOptions {
+    /// Protocol number of the header following this one
+    #[pyo3(get, set)]
+    pub next_header: u8,
+
+    /// Options as (type, data) tuples
+    #[pyo3(get, set)]
+    pub options: Vec<(u8, Vec<u8>)>,
+}
+
+#[pymethods]
+impl Ipv6HopByHopOptions {
+    /// Create a new Hop-by-Hop Options header
+    ///
+    /// Args:
+    ///     options: Options as (type, data) tuples (default: none)
+    ///     next_header: Protocol number of the following header (default: 59,
+    ///         set by Ipv6Header when the header is part of a chain)
+    #[new]
+    #[pyo3(signature = (options = Vec::new(), next_header = NO_NEXT_HEADER))]
+    pub fn new(options: Vec<(u8, Vec<u8>)>, next_header: u8) -> Self {
+        Self { next_header, options }
+    }
+
+    /// Serialize the header to bytes
+    ///
+    /// Raises:
+    ///     ValueError: If an option is longer than 255 bytes or the header
+    ///         longer than 2048 bytes
+    pub fn to_bytes(&self) -> PyResult<Vec<u8>> {
+        encode_options_header(self.next_header, &self.options).map_err(value_error)
+    }
+}
+
+/// IPv6 Destination Options header (next header 60)
+///
+/// Options are (type, data) tuples; Pad1 and PadN padding is left out when
+/// decoding and added when serializing.
+#[pyclass]
+#[derive(Clone)]
+pub struct Ipv6DestinationOptions {
+    /// Protocol number of the header following this one
+    #[pyo3(get, set)]
+    pub next_header: u8,
+
+    /// Options as (type, data) tuples
+    #[pyo3(get, set)]
+    pub options: Vec<(u8, Vec<u8>)>,
+}
+
+#[pymethods]
+impl Ipv6DestinationOptions {
+    /// Create a new Destination Options header
+    ///
+    /// Args:
+    ///     options: Options as (type, data) tuples (default: none)
+    ///     next_header: Protocol number of the following header (default: 59,
+    ///         set by Ipv6Header when the header is part of a chain)
+    #[new]
+    #[pyo3(signature = (options = Vec::new(), next_header = NO_NEXT_HEADER))]
+    pub fn new(options: Vec<(u8, Vec<u8>)>, next_header: u8) -> Self {
+        Self { next_header, options }
+    }
+
+    /// Serialize the header to bytes
+    ///
+    /// Raises:
+    ///     ValueError: If an option is longer than 255 bytes or the header
+    ///         longer than 2048 bytes
+    pub fn to_bytes(&self) -> PyResult<Vec<u8>> {
+        encode_options_header(self.next_header, &self.options).map_err(value_error)
+    }
+}
+
+/// IPv6 Routing header (next header 43)
+///
+/// The type-specific data after the segments left field is kept as bytes.
+/// For Segment Routing Headers (routing type 4) the segment list is
+/// decoded as well.
+#[pyclass]
+#[derive(Clone)]
+pub struct Ipv6RoutingHeader {
+    /// Protocol number of the header following this one
+    #[pyo3(get, set)]
+    pub next_header: u8,
+
+    /// Routing type (e.g. 4 for a Segment Routing Header)
+    #[pyo3(get, set)]
+    pub routing_type: u8,
+
+    /// Number of route segments still to be visited
+    #[pyo3(get, set)]
+    pub segments_left: u8,
+
+    /// Type-specific data after the segments left field
+    #[pyo3(get, set)]
+    pub data: Vec<u8>,
+}
+
+#[pymethods]
+impl Ipv6RoutingHeader {
+    /// Create a new Routing header
+    ///
+    /// Args:
+    ///     routing_type: Routing type
+    ///     segments_left: Number of route segments still to be visited
+    ///     data: Type-specific data; 4 bytes short of a multiple of 8
+    ///         (default: 4 zero bytes)
+    ///     next_header: Protocol number of the following header (default: 59,
+    ///         set by Ipv6Header when the header is part of a chain)
+    #[new]
+    #[pyo3(signature = (routing_type, segments_left, data = vec![0; 4], next_header = NO_NEXT_HEADER))]
+    pub fn new(routing_type: u8, segments_left: u8, data: Vec<u8>, next_header: u8) -> Self {
+        Self { next_header, routing_type, segments_left, data }
+    }
+
+    /// Create a Segment Routing Header (RFC 8754)
+    ///
+    /// Args:
+    ///     segments: Segment list in header order, i.e. the final segment
+    ///         first
+    ///     segments_left: Index of the active segment (default: the first
+    ///         segment to visit, len(segments) - 1)
+    ///     flags: Flags byte (default: 0)
+    ///     tag: Tag identifying a class of packets (default: 0)
+    ///
+    /// Raises:
+    ///     ValueError: If the segment list is empty or has more than 127 entries
+    #[staticmethod]
+    #[pyo3(signature = (segments, segments_left = None, flags = 0, tag = 0))]
+    pub fn segment_routing(
+        segments: Vec<[u8; 16]>,
+        segments_left: Option<u8>,
+        flags: u8,
+        tag: u16,
+    ) -> PyResult<Self> {
+        if segments.is_empty() || segments.len() > 127 {
+            return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "A segment list needs 1 to 127 segments (got {})",
+                segments.len()
+            )));
+        }
+        let last_entry = (segments.len() - 1) as u8;
+        let mut data = vec![last_entry, flags];
+        data.extend_from_slice(&tag.to_be_bytes());
+        for segment in &segments {
+            data.extend_from_slice(segment);
+        }
+        Ok(Self::new(SEGMENT_ROUTING, segments_left.unwrap_or(last_entry), data, NO_NEXT_HEADER))
+    }
+
+    /// Get the segment list of a Segment Routing Header (None for other
+    /// routing types)
+    ///
+    /// Segments are in header order: the final segment comes first and
+    /// segments[segments_left] is the active one.
+    #[getter]
+    pub fn segments(&self) -> Option<Vec<[u8; 16]>> {
+        if self.routing_type != SEGMENT_ROUTING || self.data.len() < 4 {
+            return None;
+        }
+        let count = usize::from(self.data[0]) + 1;
+        let segments = self.data[4..]
+            .chunks_exact(16)
+            .take(count)
+            .map(|chunk| {
+                let mut segment = [0u8; 16];
+                segment.copy_from_slice(chunk);
+                segment
+            })
+            .collect();
+        Some(segments)
+    }
+
+    /// Get the tag of a Segment Routing Header (None for other routing types)
+    #[getter]
+    pub fn tag(&self) -> Option<u16> {
+        if self.routing_type == SEGMENT_ROUTING && self.data.len() >= 4 {
+            Some(u16::from_be_bytes([self.data[2], self.data[3]]))
+        } else {
+            None
+        }
+    }
+
+    /// Serialize the header to bytes
+    ///
+    /// Raises:
+    ///     ValueError: If the header isn't a multiple of 8 bytes or is longer
+    ///         than 2048 bytes
+    pub fn to_bytes(&self) -> PyResult<Vec<u8>> {
+        self.encode().map_err(value_error)
+    }
+}
+
+impl Ipv6RoutingHeader {
+    fn encode(&self) -> Result<Vec<u8>, String> {
+        let mut buf = vec![self.next_header, 0, self.routing_type, self.segments_left];
+        buf.extend_from_slice(&self.data);
+        set_length(&mut buf, "Routing")?;
+        Ok(buf)
+    }
+}
+
+/// IPv6 Fragment header (next header 44)
+#[pyclass]
+#[derive(Clone)]
+pub struct Ipv6FragmentHeader {
+    /// Protocol number of the header following this one
+    #[pyo3(get, set)]
+    pub next_header: u8,
+
+    /// Offset of the fragment in 8-byte units
+    #[pyo3(get, set)]
+    pub fragment_offset: u16,
+
+    /// Whether more fragments follow this one
+    #[pyo3(get, set)]
+    pub more_fragments: bool,
+
+    /// Identification shared by all fragments of a packet
+    #[pyo3(get, set)]
+    pub identification: u32,
+}
+
+#[pymethods]
+impl Ipv6FragmentHeader {
+    /// Create a new Fragment header
+    ///
+    /// Args:
+    ///     identification: Identification shared by all fragments of a packet
+    ///     fragment_offset: Offset of the fragment in 8-byte units (default: 0)
+    ///     more_fragments: Whether more fragments follow (default: False)
+    ///     next_header: Protocol number of the following header (default: 59,
+    ///         set by Ipv6Header when the header is part of a chain)
+    #[new]
+    #[pyo3(signature = (identification, fragment_offset = 0, more_fragments = false, next_header = NO_NEXT_HEADER))]
+    pub fn new(identification: u32, fragment_offset: u16, more_fragments: bool, next_header: u8) -> Self {
+        Self { next_header, fragment_offset, more_fragments, identification }
+    }
+
+    /// Check if the header belongs to an actual fragment rather than a
+    /// whole packet (offset 0 without more fragments)
+    pub fn is_fragmenting_payload(&self) -> bool {
+        self.more_fragments || self.fragment_offset != 0
+    }
+
+    /// Serialize the header to bytes
+    ///
+    /// Raises:
+    ///     ValueError: If the fragment offset doesn't fit into 13 bits
+    pub fn to_bytes(&self) -> PyResult<Vec<u8>> {
+        self.encode().map_err(value_error)
+    }
+}
+
+impl Ipv6FragmentHeader {
+    fn encode(&self) -> Result<Vec<u8>, String> {
+        if self.fragment_offset > 0x1fff {
+            return Err(format!("Fragment offset {} is out of range (0-8191)", self.fragment_offset));
+        }
+        let offset = ((self.fragment_offset << 3) | u16::from(self.more_fragments)).to_be_bytes();
+        let mut buf = vec![self.next_header, 0, offset[0], offset[1]];
+        buf.extend_from_slice(&self.identification.to_be_bytes());
+        Ok(buf)
+    }
+}
+
+/// IP Authentication Header (next header 51, RFC 4302)
+#[pyclass]
+#[derive(Clone)]
+pub struct Ipv6AuthenticationHeader {
+    /// Protocol number of the header following this one
+    #[pyo3(get, set)]
+    pub next_header: u8,
+
+    /// Security parameters index
+    #[pyo3(get, set)]
+    pub spi: u32,
+
+    /// Anti-replay sequence number
+    #[pyo3(get, set)]
+    pub sequence_number: u32,
+
+    /// Integrity check value
+    #[pyo3(get, set)]
+    pub icv: Vec<u8>,
+}
+
+#[pymethods]
+impl Ipv6AuthenticationHeader {
+    /// Create a new Authentication Header
+    ///
+    /// Args:
+    ///     spi: Security parameters index
+    ///     sequence_number: Anti-replay sequence number
+    ///     icv: Integrity check value, a multiple of 4 bytes (default: none)
+    ///     next_header: Protocol number of the following header (default: 59,
+    ///         set by Ipv6Header when the header is part of a chain)
+    #[new]
+    #[pyo3(signature = (spi, sequence_number, icv = Vec::new(), next_header = NO_NEXT_HEADER))]
+    pub fn new(spi: u32, sequence_number: u32, icv: Vec<u8>, next_header: u8) -> Self {
+        Self { next_header, spi, sequence_number, icv }
+    }
+
+    /// Serialize the header to bytes
+    ///
+    /// Raises:
+    ///     ValueError: If the ICV isn't a multiple of 4 bytes or is too long
+    pub fn to_bytes(&self) -> PyResult<Vec<u8>> {
+        self.encode().map_err(value_error)
+    }
+}
+
+impl Ipv6AuthenticationHeader {
+    fn encode(&self) -> Result<Vec<u8>, String> {
+        // The length field counts 4-byte units minus 2
+        let len = 12 + self.icv.len();
+        if !len.is_multiple_of(4) || len / 4 - 2 > usize::from(u8::MAX) {
+            return Err(format!(
+                "Authentication Header ICV of {} bytes must be a multiple of 4 bytes and at most 1012 bytes",
+                self.icv.len()
+            ));
+        }
+        let mut buf = vec![self.next_header, (len / 4 - 2) as u8, 0, 0];
+        buf.extend_from_slice(&self.spi.to_be_bytes());
+        buf.extend_from_slice(&self.sequence_number.to_be_bytes());
+        buf.extend_from_slice(&self.icv);
+        Ok(buf)
+    }
+}
+
+/// One IPv6 extension header, converted to and from the extension classes
+#[derive(Clone, FromPyObject, IntoPyObject)]
+pub enum Ipv6Extension {
+    HopByHop(Ipv6HopByHopOptions),
+    Routing(Ipv6RoutingHeader),
+    Fragment(Ipv6FragmentHeader),
+    DestinationOptions(Ipv6DestinationOptions),
+    Authentication(Ipv6AuthenticationHeader),
+}
+
+impl Ipv6Extension {
+    /// IP protocol number identifying this kind of extension header
+    pub(crate) fn protocol_number(&self) -> u8 {
+        match self {
+            Self::HopByHop(_) => HOP_BY_HOP,
+            Self::Routing(_) => ROUTING,
+            Self::Fragment(_) => FRAGMENT,
+            Self::DestinationOptions(_) => DESTINATION_OPTIONS,
+            Self::Authentication(_) => AUTHENTICATION,
+        }
+    }
+
+    pub(crate) fn next_header(&self) -> u8 {
+        match self {
+            Self::HopByHop(header) => header.next_header,
+            Self::Routing(header) => header.next_header,
+            Self::Fragment(header) => header.next_header,
+            Self::DestinationOptions(header) => header.next_header,
+            Self::Authentication(header) => header.next_header,
+        }
+    }
+
+    pub(crate) fn set_next_header(&mut self, next_header: u8) {
+        match self {
+            Self::HopByHop(header) => header.next_header = next_header,
+            Self::Routing(header) => header.next_header = next_header,
+            Self::Fragment(header) => header.next_header = next_header,
+            Self::DestinationOptions(header) => header.next_header = next_header,
+            Self::Authentication(header) => header.next_header = next_header,
+        }
+    }
+
+    /// Serialize the extension header, or describe why it can't be
+    pub(crate) fn encode(&self) -> Result<Vec<u8>, String> {
+        match self {
+            Self::HopByHop(header) => encode_options_header(header.next_header, &header.options),
+            Self::Routing(header) => header.encode(),
+            Self::Fragment(header) => header.encode(),
+            Self::DestinationOptions(header) => encode_options_header(header.next_header, &header.options),
+            Self::Authentication(header) => header.encode(),
+        }
+    }
+}
+
+/// Link extension headers into a chain ending in the upper layer protocol
+///
+/// The next header field of every extension is set to the one following it.
+/// Returns the next header value of the IPv6 header itself and the
+/// serialized extensions.
+///
+/// Raises:
+///     ValueError: If an extension can't be serialized or a Hop-by-Hop
+///         Options header isn't the first extension
+pub(crate) fn chain_extensions(
+    extensions: &mut [Ipv6Extension],
+    upper_layer_protocol: u8,
+) -> PyResult<(u8, Vec<Vec<u8>>)> {
+    let numbers: Vec<u8> = extensions.iter().map(Ipv6Extension::protocol_number).collect();
+    if numbers.iter().skip(1).any(|number| *number == HOP_BY_HOP) {
+        return Err(pyo3::exceptions::PyValueError::new_err(
+            "A Hop-by-Hop Options header must be the first extension header",
+        ));
+    }
+    let mut encoded = Vec::with_capacity(extensions.len());
+    for (i, extension) in extensions.iter_mut().enumerate() {
+        extension.set_next_header(numbers.get(i + 1).copied().unwrap_or(upper_layer_protocol));
+        encoded.push(extension.encode().map_err(value_error)?);
+    }
+    Ok((numbers.first().copied().unwrap_or(upper_layer_protocol), encoded))
+}
+
+/// An extension header along with its bytes as captured
+pub(crate) type CapturedExtension = (Ipv6Extension, Vec<u8>);
+
+/// Read the extension headers following an IPv6 header
+///
+/// Returns the extensions along with their bytes as captured, the upper
+/// layer protocol number and the rest of the slice. Decoding stops after
+/// the Fragment header of an actual fragment, as what follows is fragment
+/// data rather than headers.
+pub(crate) fn decode_extensions(
+    mut next_header: u8,
+    mut slice: &[u8],
+) -> Result<(Vec<CapturedExtension>, u8, &[u8]), ReadError> {
+    let mut extensions = Vec::new();
+    while matches!(next_header, HOP_BY_HOP | ROUTING | FRAGMENT | DESTINATION_OPTIONS | AUTHENTICATION) {
+        if next_header == HOP_BY_HOP && !extensions.is_empty() {
+            return Err(ReadError::Ipv6HopByHopHeaderNotAtStart);
+        }
+        if slice.len() < 8 {
+            return Err(ReadError::UnexpectedEndOfSlice(8));
+        }
+        let len = match next_header {
+            FRAGMENT => 8,
+            AUTHENTICATION if slice[1] == 0 => {
+                return Err(ReadError::IpAuthenticationHeaderTooSmallPayloadLength(0));
+            },
+            AUTHENTICATION => (usize::from(slice[1]) + 2) * 4,
+            _ => (usize::from(slice[1]) + 1) * 8,
+        };
+        if slice.len() < len {
+            return Err(ReadError::UnexpectedEndOfSlice(len));
+        }
+
+        let (header, rest) = slice.split_at(len);
+        let extension = match next_header {
+            HOP_BY_HOP => Ipv6Extension::HopByHop(Ipv6HopByHopOptions::new(decode_options(&header[2..]), header[0])),
+            ROUTING => Ipv6Extension::Routing(Ipv6RoutingHeader::new(header[2], header[3], header[4..].to_vec(), header[0])),
+            FRAGMENT => {
+                let offset = u16::from_be_bytes([header[2], header[3]]);
+                Ipv6Extension::Fragment(Ipv6FragmentHeader::new(
+                    u32::from_be_bytes([header[4], header[5], header[6], header[7]]),
+                    offset >> 3,
+                    offset & 1 != 0,
+                    header[0],
+                ))
+            },
+            DESTINATION_OPTIONS => {
+                Ipv6Extension::DestinationOptions(Ipv6DestinationOptions::new(decode_options(&header[2..]), header[0]))
+            },
+            _ => Ipv6Extension::Authentication(Ipv6AuthenticationHeader::new(
+                u32::from_be_bytes([header[4], header[5], header[6], header[7]]),
+                u32::from_be_bytes([header[8], header[9], header[10], header[11]]),
+                header[12..].to_vec(),
+                header[0],
+            )),
+        };
+        next_header = extension.next_header();
+        let fragmenting = matches!(&extension, Ipv6Extension::Fragment(fragment) if fragment.is_fragmenting_payload());
+        extensions.push((extension, header.to_vec()));
+        slice = rest;
+        if fragmenting {
+            break;
+        }
+    }
+    Ok((extensions, next_header, slice))
+}
+
+/// Decode the options of a Hop-by-Hop or Destination Options header
+///
+/// Padding is skipped. A malformed option and everything after it is
+/// returned as one option.
+fn decode_options(mut area: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    let mut options = Vec::new();
+    while let Some(&option_type) = area.first() {
+        if option_type == PAD1 {
+            area = &area[1..];
+            continue;
+        }
+        let len = area.get(1).map_or(usize::MAX, |len| usize::from(*len) + 2);
+        if len > area.len() {
+            options.push((option_type, area[1..].to_vec()));
+            break;
+        }
+        if option_type != PADN {
+            options.push((option_type, area[2..len].to_vec()));
+        }
+        area = &area[len..];
+    }
+    options
+}
+
+/// Serialize a Hop-by-Hop or Destination Options header, padding it to a
+/// multiple of 8 bytes
+fn encode_options_header(next_header: u8, options: &[(u8, Vec<u8>)]) -> Result<Vec<u8>, String> {
+    let mut buf = vec![next_header, 0];
+    for (option_type, data) in options {
+        if data.len() > usize::from(u8::MAX) {
+            return Err(format!("Option {} has {} bytes of data, at most 255 are allowed", option_type, data.len()));
+        }
+        buf.push(*option_type);
+        buf.push(data.len() as u8);
+        buf.extend_from_slice(data);
+    }
+    match (8 - buf.len() % 8) % 8 {
+        0 => {},
+        1 => buf.push(PAD1),
+        padding => {
+            buf.extend_from_slice(&[PADN, (padding - 2) as u8]);
+            buf.resize(buf.len() + padding - 2, 0);
+        },
+    }
+    set_length(&mut buf, "Options")?;
+    Ok(buf)
+}
+
+/// Fill in the length field of an extension header counted in 8-byte units
+fn set_length(buf: &mut [u8], name: &str) -> Result<(), String> {
+    if !buf.len().is_multiple_of(8) || buf.len() > MAX_EXTENSION_LEN {
+        return Err(format!(
+            "{} header of {} bytes must be a multiple of 8 bytes and at most {} bytes",
+            name, buf.len(), MAX_EXTENSION_LEN
+        ));
+    }
+    buf[1] = (buf.len() / 8 - 1) as u8;
+    Ok(())
+}
+
+fn value_error(message: String) -> PyErr {
+    pyo3::exceptions::PyValueError::new_err(message)
+}
@@ -0,0 +1,215 @@
+use pyo3::prelude::*;
+
+/// Size of the link-layer address field in both header versions
+const ADDRESS_FIELD_LEN: usize = 8;
+
+/// Python wrapper for a Linux "cooked" capture header
+/// Represents the pseudo link-layer header of LINKTYPE_LINUX_SLL (version 1)
+/// and LINKTYPE_LINUX_SLL2 (version 2) captures, as written by
+/// `tcpdump -i any`
+#[pyclass]
+#[derive(Clone)]
+pub struct LinuxSllHeader {
+    // etherparse has no cooked capture support, so the fields are kept here
+    version: u8,
+    packet_type: u16,
+    arphrd_type: u16,
+    // Length field as captured; may exceed the 8 stored address bytes
+    address_length: u16,
+    address: [u8; ADDRESS_FIELD_LEN],
+    protocol: u16,
+    interface_index: u32,
+}
+
+#[pymethods]
+impl LinuxSllHeader {
+    /// Create a new LinuxSllHeader
+    ///
+    /// An SLL2 header is created if an interface index is given, an SLL
+    /// header otherwise.
+    ///
+    /// Args:
+    ///     packet_type: Direction/kind of the packet (0 = to us, 1 = broadcast,
+    ///         2 = multicast, 3 = to someone else, 4 = sent by us)
+    ///     arphrd_type: ARPHRD_* type of the interface (e.g. 1 for Ethernet)
+    ///     address: Link-layer source address, at most 8 bytes
+    ///     protocol: Protocol of the payload, usually an EtherType (e.g. 0x0800)
+    ///     interface_index: Index of the capturing interface (SLL2 only)
+    ///
+    /// Raises:
+    ///     ValueError: If the address is longer than 8 bytes, or the packet
+    ///         type doesn't fit into the single byte SLL2 has for it
+    #[new]
+    #[pyo3(signature = (packet_type, arphrd_type, address, protocol, interface_index = None))]
+    pub fn new(
+        packet_type: u16,
+        arphrd_type: u16,
+        address: Vec<u8>,
+        protocol: u16,
+        interface_index: Option<u32>,
+    ) -> PyResult<Self> {
+        if address.len() > ADDRESS_FIELD_LEN {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                format!("Link-layer address is {} bytes long, at most 8 are allowed", address.len())
+            ));
+        }
+        if interface_index.is_some() && packet_type > u16::from(u8::MAX) {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                format!("SLL2 packet type {} is out of range (0-255)", packet_type)
+            ));
+        }
+        let mut address_field = [0u8; ADDRESS_FIELD_LEN];
+        address_field[..address.len()].copy_from_slice(&address);
+        Ok(Self {
+            version: if interface_index.is_some() { 2 } else { 1 },
+            packet_type,
+            arphrd_type,
+            address_length: address.len() as u16,
+            address: address_field,
+            protocol,
+            interface_index: interface_index.unwrap_or(0),
+        })
+    }
+
+    /// Get the header version (1 for SLL, 2 for SLL2)
+    #[getter]
+    pub fn version(&self) -> u8 {
+        self.version
+    }
+
+    /// Get the packet type (0 = to us, 1 = broadcast, 2 = multicast,
+    /// 3 = to someone else, 4 = sent by us)
+    #[getter]
+    pub fn packet_type(&self) -> u16 {
+        self.packet_type
+    }
+
+    /// Get the packet type as a name
+    ///
+    /// Returns "host", "broadcast", "multicast", "otherhost", "outgoing" or
+    /// None for other values.
+    #[getter]
+    pub fn packet_type_name(&self) -> Option<&'static str> {
+        match self.packet_type {
+            0 => Some("host"),
+            1 => Some("broadcast"),
+            2 => Some("multicast"),
+            3 => Some("otherhost"),
+            4 => Some("outgoing"),
+            _ => None,
+        }
+    }
+
+    /// Get the ARPHRD_* type of the capturing interface
+    #[getter]
+    pub fn arphrd_type(&self) -> u16 {
+        self.arphrd_type
+    }
+
+    /// Get the link-layer address length as captured
+    #[getter]
+    pub fn address_length(&self) -> u16 {
+        self.address_length
+    }
+
+    /// Get the link-layer source address (at most 8 bytes are captured)
+    #[getter]
+    pub fn address(&self) -> Vec<u8> {
+        let len = usize::from(self.address_length).min(ADDRESS_FIELD_LEN);
+        self.address[..len].to_vec()
+    }
+
+    /// Get the protocol of the payload (an EtherType for IP traffic)
+    #[getter]
+    pub fn protocol(&self) -> u16 {
+        self.protocol
+    }
+
+    /// Get the index of the capturing interface (None for SLL headers)
+    #[getter]
+    pub fn interface_index(&self) -> Option<u32> {
+        if self.version == 2 {
+            Some(self.interface_index)
+        } else {
+            None
+        }
+    }
+
+    /// Get the size of the header in bytes (16 for SLL, 20 for SLL2)
+    pub fn header_len(&self) -> usize {
+        if self.version == 2 { 20 } else { 16 }
+    }
+
+    /// Serialize the header to bytes
+    ///
+    /// Returns:
+    ///     A vector of bytes representing the header
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(self.header_len());
+        if self.version == 2 {
+            buf.extend_from_slice(&self.protocol.to_be_bytes());
+            buf.extend_from_slice(&[0, 0]); // reserved
+            buf.extend_from_slice(&self.interface_index.to_be_bytes());
+            buf.extend_from_slice(&self.arphrd_type.to_be_bytes());
+            buf.push(self.packet_type as u8);
+            buf.push(self.address_length as u8);
+            buf.extend_from_slice(&self.address);
+        } else {
+            buf.extend_from_slice(&self.packet_type.to_be_bytes());
+            buf.extend_from_slice(&self.arphrd_type.to_be_bytes());
+            buf.extend_from_slice(&self.address_length.to_be_bytes());
+            buf.extend_from_slice(&self.address);
+            buf.extend_from_slice(&self.protocol.to_be_bytes());
+        }
+        buf
+    }
+}
+
+impl LinuxSllHeader {
+    /// Set the protocol of the payload
+    pub(crate) fn set_protocol(&mut self, protocol: u16) {
+        self.protocol = protocol;
+    }
+
+    /// Read an SLL header, returning it and the rest of the slice
+    ///
+    /// Returns None if the slice is shorter than the header.
+    pub(crate) fn from_slice_v1(slice: &[u8]) -> Option<(Self, &[u8])> {
+        if slice.len() < 16 {
+            return None;
+        }
+        let mut address = [0u8; ADDRESS_FIELD_LEN];
+        address.copy_from_slice(&slice[6..14]);
+        let header = Self {
+            version: 1,
+            packet_type: u16::from_be_bytes([slice[0], slice[1]]),
+            arphrd_type: u16::from_be_bytes([slice[2], slice[3]]),
+            address_length: u16::from_be_bytes([slice[4], slice[5]]),
+            address,
+            protocol: u16::from_be_bytes([slice[14], slice[15]]),
+            interface_index: 0,
+        };
+        Some((header, &slice[16..]))
+    }
+
+    /// Read an SLL2 header, returning it and the rest of the slice
+    ///
+    /// Returns None if the slice is shorter than the header.
+    pub(crate) fn from_slice_v2(slice: &[u8]) -> Option<(Self, &[u8])> {
+        if slice.len() < 20 {
+            return None;
+        }
+        let mut address = [0u8; ADDRESS_FIELD_LEN];
+        address.copy_from_slice(&slice[12..20]);
+        let header = Self {
+            version: 2,
+            packet_type: u16::from(slice[10]),
+            arphrd_type: u16::from_be_bytes([slice[8], slice[9]]),
+            address_length: u16::from(slice[11]),
+            address,
+            protocol: u16::from_be_bytes([slice[0], slice[1]]),
+            interface_index: u32::from_be_bytes([slice[4], slice[5], slice[6], slice[7]]),
+        };
+        Some((header, &slice[20..]))
+    }
+}
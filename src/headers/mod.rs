@@ -3,21 +3,196 @@
 //! This module contains wrappers for various network protocol headers
 //! from the etherparse crate, providing a Python-friendly API.
 
+use pyo3::prelude::*;
+
 // Declare submodules
+mod arp;
 mod ethernet;
+mod icmpv4;
+mod icmpv6;
 mod ipv4;
+mod ipv4_options;
 mod ipv6;
+mod ipv6_extensions;
+mod linux_sll;
 mod tcp;
+mod tcp_options;
 mod udp;
+mod vlan;
 
 // Re-export header structs so they can be imported directly from pytherparse.headers
+pub use arp::ArpHeader;
 pub use ethernet::Ethernet2Header;
+pub use icmpv4::Icmpv4Header;
+pub use icmpv6::{Icmpv6Header, NdpOption};
 pub use ipv4::Ipv4Header;
+pub use ipv4_options::{
+    Ipv4OptionRaw, Ipv4OptionRecordRoute, Ipv4OptionRouterAlert, Ipv4OptionSecurity,
+    Ipv4OptionSourceRoute, Ipv4OptionTimestamp,
+};
 pub use ipv6::Ipv6Header;
+pub use ipv6_extensions::{
+    Ipv6AuthenticationHeader, Ipv6DestinationOptions, Ipv6FragmentHeader, Ipv6HopByHopOptions,
+    Ipv6RoutingHeader,
+};
+pub(crate) use ipv6_extensions::decode_extensions;
+pub use linux_sll::LinuxSllHeader;
 pub use tcp::TcpHeader;
+pub use tcp_options::{
+    TcpOptionFastOpen, TcpOptionMptcp, TcpOptionMss, TcpOptionRaw, TcpOptionSack, TcpOptionSackPermitted,
+    TcpOptionTimestamp, TcpOptionWindowScale,
+};
 pub use udp::UdpHeader;
+pub use vlan::VlanHeader;
 
 // This pattern allows users to import like:
 // from pytherparse.headers import Ethernet2Header, Ipv4Header
 // instead of:
-// from pytherparse.headers.ethernet import Ethernet2Header
\ No newline at end of file
+// from pytherparse.headers.ethernet import Ethernet2Header
+
+/// Check a value assigned from Python against the range of a header field
+///
+/// Raises:
+///     ValueError: If the value is negative or larger than max
+pub(crate) fn field_value<T: TryFrom<u64>>(name: &str, value: i64, max: u64) -> pyo3::PyResult<T> {
+    u64::try_from(value)
+        .ok()
+        .filter(|value| *value <= max)
+        .and_then(|value| T::try_from(value).ok())
+        .ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "{} {} is out of range (0-{})",
+                name, value, max
+            ))
+        })
+}
+
+// End-of-options and no-operation options, shared by IPv4 and TCP
+const END_OF_OPTIONS: u8 = 0;
+const NO_OPERATION: u8 = 1;
+
+/// Maximum size of the IPv4 and TCP options areas in bytes
+const MAX_OPTIONS_LEN: usize = 40;
+
+/// Decode an IPv4 or TCP options area, which share their layout: single
+/// byte end-of-options and no-operation options, and type-length-value
+/// options for everything else
+///
+/// No-operation options are skipped and decoding ends at an end-of-options
+/// option. Every other option is passed to decode as its type and the bytes
+/// after the length byte. A malformed option and everything after it is
+/// passed to raw as its type and the bytes after the type.
+pub(crate) fn decode_option_area<T>(
+    mut area: &[u8],
+    decode: impl Fn(u8, &[u8]) -> T,
+    raw: impl Fn(u8, Vec<u8>) -> T,
+) -> Vec<T> {
+    let mut options = Vec::new();
+    while let Some(&option_type) = area.first() {
+        match option_type {
+            END_OF_OPTIONS => break,
+            NO_OPERATION => {
+                area = &area[1..];
+                continue;
+            },
+            _ => {},
+        }
+
+        let len = area.get(1).map_or(0, |len| usize::from(*len));
+        if len < 2 || len > area.len() {
+            options.push(raw(option_type, area[1..].to_vec()));
+            break;
+        }
+        options.push(decode(option_type, &area[2..len]));
+        area = &area[len..];
+    }
+    options
+}
+
+/// Concatenate serialized IPv4 or TCP options into an options area padded
+/// to a multiple of 4 bytes
+///
+/// Raises:
+///     ValueError: If the options don't fit into the 40 byte options area
+pub(crate) fn encode_option_area(protocol: &str, options: impl Iterator<Item = Vec<u8>>) -> pyo3::PyResult<Vec<u8>> {
+    let mut area: Vec<u8> = options.flatten().collect();
+    while !area.len().is_multiple_of(4) {
+        area.push(END_OF_OPTIONS);
+    }
+    if area.len() > MAX_OPTIONS_LEN {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "{} options take {} bytes, at most {} are allowed",
+            protocol, area.len(), MAX_OPTIONS_LEN
+        )));
+    }
+    Ok(area)
+}
+
+/// Compute the Internet checksum (RFC 1071) over a sequence of byte chunks
+///
+/// The chunks are summed as if they were one contiguous buffer.
+pub(crate) fn internet_checksum(chunks: &[&[u8]]) -> u16 {
+    let mut sum: u64 = 0;
+    let mut odd: Option<u8> = None;
+    for chunk in chunks {
+        for &byte in chunk.iter() {
+            match odd.take() {
+                Some(high) => sum += u64::from(u16::from_be_bytes([high, byte])),
+                None => odd = Some(byte),
+            }
+        }
+    }
+    if let Some(high) = odd {
+        sum += u64::from(u16::from_be_bytes([high, 0]));
+    }
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Pack an ICMP echo identifier and sequence number into the rest of the header
+pub(crate) fn id_and_sequence(identifier: u16, sequence: u16) -> [u8; 4] {
+    let id = identifier.to_be_bytes();
+    let seq = sequence.to_be_bytes();
+    [id[0], id[1], seq[0], seq[1]]
+}
+
+/// Compute a TCP or UDP checksum over the segment and the IP pseudo header
+///
+/// The pseudo header is laid out for IPv4 or IPv6 depending on the length
+/// of the addresses.
+pub(crate) fn transport_checksum(source: &[u8], destination: &[u8], protocol: u8, segment: &[&[u8]]) -> u16 {
+    let length = segment.iter().map(|chunk| chunk.len()).sum::<usize>() as u32;
+    let mut pseudo_header = Vec::with_capacity(8);
+    if source.len() == 4 {
+        pseudo_header.extend_from_slice(&[0, protocol]);
+        pseudo_header.extend_from_slice(&(length as u16).to_be_bytes());
+    } else {
+        pseudo_header.extend_from_slice(&length.to_be_bytes());
+        pseudo_header.extend_from_slice(&[0, 0, 0, protocol]);
+    }
+
+    let mut chunks = vec![source, destination, &pseudo_header];
+    chunks.extend_from_slice(segment);
+    internet_checksum(&chunks)
+}
+
+/// An IPv4 or IPv6 header passed from Python, used for pseudo header checksums
+#[derive(FromPyObject)]
+pub enum IpHeader {
+    V4(Ipv4Header),
+    V6(Ipv6Header),
+}
+
+impl IpHeader {
+    /// Get the source and destination address used in the pseudo header
+    ///
+    /// For IPv6 the destination is the final one of a routing header.
+    pub(crate) fn pseudo_header_addresses(&self) -> (Vec<u8>, Vec<u8>) {
+        match self {
+            Self::V4(ip) => (ip.source().to_vec(), ip.destination().to_vec()),
+            Self::V6(ip) => (ip.source().to_vec(), ip.final_destination().to_vec()),
+        }
+    }
+}
@@ -1,4 +1,9 @@
 use pyo3::prelude::*;
+use crate::headers::{field_value, transport_checksum, IpHeader};
+use crate::headers::tcp_options::{decode_options, encode_options, TcpOption};
+
+/// IP protocol number of TCP, used in the pseudo header
+const TCP: u8 = 6;
 
 /// Python wrapper for etherparse::TcpHeader
 /// Represents a TCP header
@@ -8,6 +13,8 @@ pub struct TcpHeader {
     // Store the inner etherparse header for delegation and preservation
     // of the original structure's functionality
     inner: etherparse::TcpHeader,
+    // Checksum check made when the segment was parsed
+    pub(crate) checksum_valid: Option<bool>,
 }
 
 #[pymethods]
@@ -20,15 +27,20 @@ impl TcpHeader {
     ///     sequence_number: Sequence number (default: 0)
     ///     acknowledgment_number: Acknowledgment number (default: 0)
     ///     window_size: Window size in bytes (default: 64240)
+    ///     options: List of TcpOption* objects (default: none)
+    ///
+    /// Raises:
+    ///     ValueError: If the options don't fit into 40 bytes
     #[new]
-    #[pyo3(signature = (source_port, destination_port, sequence_number = 0, acknowledgment_number = 0, window_size = 64240))]
+    #[pyo3(signature = (source_port, destination_port, sequence_number = 0, acknowledgment_number = 0, window_size = 64240, options = Vec::new()))]
     pub fn new(
         source_port: u16, 
         destination_port: u16, 
         sequence_number: u32, 
         acknowledgment_number: u32,
-        window_size: u16
-    ) -> Self {
+        window_size: u16,
+        options: Vec<TcpOption>,
+    ) -> PyResult<Self> {
         // Create a default TCP header
         let mut header = etherparse::TcpHeader::default();
         
@@ -40,7 +52,9 @@ impl TcpHeader {
         header.window_size = window_size;
         
         // Return the wrapped header
-        Self { inner: header }
+        let mut header = Self { inner: header, checksum_valid: None };
+        header.set_options(options)?;
+        Ok(header)
     }
 
     /// Get the source port
@@ -79,6 +93,21 @@ impl TcpHeader {
         self.inner.urgent_pointer
     }
     
+    /// Get the checksum field
+    #[getter]
+    pub fn checksum(&self) -> u16 {
+        self.inner.checksum
+    }
+    
+    /// Check if the checksum was correct when the packet was parsed
+    ///
+    /// None for headers built in Python and for segments cut off by the
+    /// capture length.
+    #[getter]
+    pub fn checksum_valid(&self) -> Option<bool> {
+        self.checksum_valid
+    }
+    
     /// Get the header length in 32-bit words
     #[getter]
     pub fn data_offset(&self) -> u8 {
@@ -91,6 +120,64 @@ impl TcpHeader {
         self.inner.header_len() as u8
     }
     
+    /// Get the options as TcpOption* objects
+    ///
+    /// MSS, window scale, SACK permitted, SACK, timestamps, Fast Open and
+    /// MPTCP options are decoded; any other option is returned as a
+    /// TcpOptionRaw. No-operation padding is left out.
+    #[getter]
+    pub fn options(&self) -> Vec<TcpOption> {
+        decode_options(self.inner.options())
+    }
+
+    /// Replace the options
+    ///
+    /// The options area is padded to a multiple of 4 bytes and the data
+    /// offset grows or shrinks to match.
+    ///
+    /// Raises:
+    ///     ValueError: If the options don't fit into 40 bytes
+    #[setter]
+    pub fn set_options(&mut self, options: Vec<TcpOption>) -> PyResult<()> {
+        let area = encode_options(&options)?;
+        self.inner.set_options_raw(&area).map_err(|e| {
+            pyo3::exceptions::PyValueError::new_err(format!("Invalid TCP options: {}", e))
+        })
+    }
+
+    /// Get the raw bytes of the options area, including padding
+    #[getter]
+    pub fn options_bytes(&self) -> Vec<u8> {
+        self.inner.options().to_vec()
+    }
+
+    /// Get the maximum segment size from the MSS option (None without one)
+    #[getter]
+    pub fn mss(&self) -> Option<u16> {
+        self.options().into_iter().find_map(|option| match option {
+            TcpOption::Mss(option) => Some(option.mss),
+            _ => None,
+        })
+    }
+
+    /// Get the shift count from the window scale option (None without one)
+    #[getter]
+    pub fn window_scale(&self) -> Option<u8> {
+        self.options().into_iter().find_map(|option| match option {
+            TcpOption::WindowScale(option) => Some(option.shift),
+            _ => None,
+        })
+    }
+
+    /// Get the (TSval, TSecr) pair from the timestamps option (None without one)
+    #[getter]
+    pub fn timestamp(&self) -> Option<(u32, u32)> {
+        self.options().into_iter().find_map(|option| match option {
+            TcpOption::Timestamp(option) => Some((option.tsval, option.tsecr)),
+            _ => None,
+        })
+    }
+    
     // TCP Flag getters
     
     /// Get the SYN flag (synchronize sequence numbers)
@@ -141,6 +228,85 @@ impl TcpHeader {
         self.inner.cwr
     }
     
+    /// Get the NS flag (ECN-nonce concealment protection)
+    #[getter]
+    pub fn ns(&self) -> bool {
+        self.inner.ns
+    }
+    
+    // Field setters
+    
+    /// Set the source port
+    ///
+    /// Raises:
+    ///     ValueError: If the port is out of range (0-65535)
+    #[setter]
+    pub fn set_source_port(&mut self, port: i64) -> PyResult<()> {
+        self.inner.source_port = field_value("Source port", port, u16::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Set the destination port
+    ///
+    /// Raises:
+    ///     ValueError: If the port is out of range (0-65535)
+    #[setter]
+    pub fn set_destination_port(&mut self, port: i64) -> PyResult<()> {
+        self.inner.destination_port = field_value("Destination port", port, u16::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Set the sequence number
+    ///
+    /// Raises:
+    ///     ValueError: If the value doesn't fit into 32 bits
+    #[setter]
+    pub fn set_sequence_number(&mut self, sequence_number: i64) -> PyResult<()> {
+        self.inner.sequence_number = field_value("Sequence number", sequence_number, u32::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Set the acknowledgment number
+    ///
+    /// Raises:
+    ///     ValueError: If the value doesn't fit into 32 bits
+    #[setter]
+    pub fn set_acknowledgment_number(&mut self, acknowledgment_number: i64) -> PyResult<()> {
+        self.inner.acknowledgment_number =
+            field_value("Acknowledgment number", acknowledgment_number, u32::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Set the checksum field
+    ///
+    /// Raises:
+    ///     ValueError: If the checksum doesn't fit into 16 bits
+    #[setter]
+    pub fn set_checksum(&mut self, checksum: i64) -> PyResult<()> {
+        self.inner.checksum = field_value("Checksum", checksum, u16::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Set the window size
+    ///
+    /// Raises:
+    ///     ValueError: If the value is out of range (0-65535)
+    #[setter]
+    pub fn set_window_size(&mut self, window_size: i64) -> PyResult<()> {
+        self.inner.window_size = field_value("Window size", window_size, u16::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Set the urgent pointer
+    ///
+    /// Raises:
+    ///     ValueError: If the value is out of range (0-65535)
+    #[setter]
+    pub fn set_urgent_pointer(&mut self, urgent_pointer: i64) -> PyResult<()> {
+        self.inner.urgent_pointer = field_value("Urgent pointer", urgent_pointer, u16::MAX.into())?;
+        Ok(())
+    }
+    
     // TCP Flag setters
     
     /// Set the SYN flag (synchronize sequence numbers)
@@ -178,19 +344,75 @@ impl TcpHeader {
     pub fn set_urg(&mut self, value: bool) {
         self.inner.urg = value;
     }
+    
+    /// Set the ECE flag (ECN-Echo)
+    #[setter]
+    pub fn set_ece(&mut self, value: bool) {
+        self.inner.ece = value;
+    }
+    
+    /// Set the CWR flag (Congestion Window Reduced)
+    #[setter]
+    pub fn set_cwr(&mut self, value: bool) {
+        self.inner.cwr = value;
+    }
+    
+    /// Set the NS flag (ECN-nonce concealment protection)
+    #[setter]
+    pub fn set_ns(&mut self, value: bool) {
+        self.inner.ns = value;
+    }
+
+    /// Calculate and set the checksum using the IPv4 pseudo header
+    /// 
+    /// Args:
+    ///     source_ip: Source IPv4 address
+    ///     dest_ip: Destination IPv4 address
+    ///     payload: TCP payload data
+    ///
+    /// Returns:
+    ///     int: The new checksum
+    pub fn calc_checksum_ipv4(&mut self, source_ip: [u8; 4], dest_ip: [u8; 4], payload: &[u8]) -> u16 {
+        self.calc_checksum(&source_ip, &dest_ip, payload)
+    }
+    
+    /// Calculate and set the checksum using the IPv6 pseudo header
+    ///
+    /// With a routing header, pass the final destination as dest_ip.
+    /// 
+    /// Args:
+    ///     source_ip: Source IPv6 address
+    ///     dest_ip: Destination IPv6 address
+    ///     payload: TCP payload data
+    ///
+    /// Returns:
+    ///     int: The new checksum
+    pub fn calc_checksum_ipv6(&mut self, source_ip: [u8; 16], dest_ip: [u8; 16], payload: &[u8]) -> u16 {
+        self.calc_checksum(&source_ip, &dest_ip, payload)
+    }
+    
+    /// Check the checksum against an IP header and the payload
+    ///
+    /// Args:
+    ///     ip: The Ipv4Header or Ipv6Header the segment was sent with
+    ///     payload: TCP payload data
+    ///
+    /// Returns:
+    ///     bool: True if the checksum is correct
+    pub fn verify_checksum(&self, ip: IpHeader, payload: &[u8]) -> bool {
+        let (source, destination) = ip.pseudo_header_addresses();
+        transport_checksum(&source, &destination, TCP, &[&self.to_bytes(), payload]) == 0
+    }
 
     /// Serialize the TCP header to bytes
     /// 
     /// Returns:
     ///     A vector of bytes representing the header
     pub fn to_bytes(&self) -> Vec<u8> {
-        // Calculate the size of the header (minimum 20 bytes)
-        let size = self.inner.header_len() as usize;
+        // Create a buffer with room for the header (minimum 20 bytes)
+        let mut buf = Vec::with_capacity(self.inner.header_len() as usize);
         
-        // Create a buffer with the right size
-        let mut buf = vec![0u8; size];
-        
-        // Use etherparse's own serialization
+        // Use etherparse's own serialization, which appends to the buffer
         self.inner.write(&mut buf).unwrap();
         
         // Return the buffer
@@ -198,11 +420,28 @@ impl TcpHeader {
     }
 }
 
+impl TcpHeader {
+    fn calc_checksum(&mut self, source: &[u8], destination: &[u8], payload: &[u8]) -> u16 {
+        self.inner.checksum = 0;
+        self.inner.checksum = transport_checksum(source, destination, TCP, &[&self.to_bytes(), payload]);
+        self.inner.checksum
+    }
+
+    /// Get the flags as they appear in byte 13 of the header, CWR to FIN
+    pub(crate) fn flags(&self) -> u8 {
+        [self.inner.fin, self.inner.syn, self.inner.rst, self.inner.psh,
+         self.inner.ack, self.inner.urg, self.inner.ece, self.inner.cwr]
+            .iter()
+            .enumerate()
+            .fold(0, |flags, (bit, set)| flags | (u8::from(*set) << bit))
+    }
+}
+
 /// Convert from etherparse::TcpHeader to our TcpHeader
 /// This is used when parsing packets
 impl From<etherparse::TcpHeader> for TcpHeader {
     fn from(header: etherparse::TcpHeader) -> Self {
-        Self { inner: header }
+        Self { inner: header, checksum_valid: None }
     }
 }
 
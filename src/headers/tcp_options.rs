@@ -0,0 +1,345 @@
+use pyo3::prelude::*;
+use crate::headers::{decode_option_area, encode_option_area};
+
+// TCP option kinds (IANA "TCP Option Kind Numbers")
+const MAXIMUM_SEGMENT_SIZE: u8 = 2;
+const WINDOW_SCALE: u8 = 3;
+const SACK_PERMITTED: u8 = 4;
+const SACK: u8 = 5;
+const TIMESTAMP: u8 = 8;
+const MPTCP: u8 = 30;
+const FAST_OPEN: u8 = 34;
+
+/// TCP Maximum Segment Size option (kind 2)
+#[pyclass]
+#[derive(Clone)]
+pub struct TcpOptionMss {
+    /// Largest segment the sender is willing to receive
+    #[pyo3(get, set)]
+    pub mss: u16,
+}
+
+#[pymethods]
+impl TcpOptionMss {
+    /// Create a new option
+    ///
+    /// Args:
+    ///     mss: Largest segment the sender is willing to receive
+    #[new]
+    pub fn new(mss: u16) -> Self {
+        Self { mss }
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mss = self.mss.to_be_bytes();
+        vec![MAXIMUM_SEGMENT_SIZE, 4, mss[0], mss[1]]
+    }
+}
+
+/// TCP Window Scale option (kind 3, RFC 7323)
+#[pyclass]
+#[derive(Clone)]
+pub struct TcpOptionWindowScale {
+    /// Shift count applied to the window size of later segments
+    #[pyo3(get, set)]
+    pub shift: u8,
+}
+
+#[pymethods]
+impl TcpOptionWindowScale {
+    /// Create a new option
+    ///
+    /// Args:
+    ///     shift: Shift count applied to the window size (0-14)
+    #[new]
+    pub fn new(shift: u8) -> Self {
+        Self { shift }
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        vec![WINDOW_SCALE, 3, self.shift]
+    }
+}
+
+/// TCP SACK Permitted option (kind 4, RFC 2018)
+#[pyclass]
+#[derive(Clone)]
+pub struct TcpOptionSackPermitted {}
+
+#[pymethods]
+impl TcpOptionSackPermitted {
+    /// Create a new option
+    #[new]
+    pub fn new() -> Self {
+        Self {}
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        vec![SACK_PERMITTED, 2]
+    }
+}
+
+impl Default for TcpOptionSackPermitted {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// TCP SACK option (kind 5, RFC 2018)
+#[pyclass]
+#[derive(Clone)]
+pub struct TcpOptionSack {
+    /// Received blocks as (left edge, right edge) sequence number tuples
+    #[pyo3(get, set)]
+    pub blocks: Vec<(u32, u32)>,
+}
+
+#[pymethods]
+impl TcpOptionSack {
+    /// Create a new option
+    ///
+    /// Args:
+    ///     blocks: Received blocks as (left edge, right edge) tuples, at most 4
+    #[new]
+    pub fn new(blocks: Vec<(u32, u32)>) -> Self {
+        Self { blocks }
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![SACK, (2 + 8 * self.blocks.len()) as u8];
+        for (left, right) in &self.blocks {
+            buf.extend_from_slice(&left.to_be_bytes());
+            buf.extend_from_slice(&right.to_be_bytes());
+        }
+        buf
+    }
+}
+
+/// TCP Timestamps option (kind 8, RFC 7323)
+#[pyclass]
+#[derive(Clone)]
+pub struct TcpOptionTimestamp {
+    /// Timestamp value of the sender (TSval)
+    #[pyo3(get, set)]
+    pub tsval: u32,
+
+    /// Most recent timestamp value received from the peer (TSecr)
+    #[pyo3(get, set)]
+    pub tsecr: u32,
+}
+
+#[pymethods]
+impl TcpOptionTimestamp {
+    /// Create a new option
+    ///
+    /// Args:
+    ///     tsval: Timestamp value of the sender
+    ///     tsecr: Echoed timestamp of the peer (default: 0)
+    #[new]
+    #[pyo3(signature = (tsval, tsecr = 0))]
+    pub fn new(tsval: u32, tsecr: u32) -> Self {
+        Self { tsval, tsecr }
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![TIMESTAMP, 10];
+        buf.extend_from_slice(&self.tsval.to_be_bytes());
+        buf.extend_from_slice(&self.tsecr.to_be_bytes());
+        buf
+    }
+}
+
+/// TCP Fast Open option (kind 34, RFC 7413)
+///
+/// An empty cookie requests a cookie from the server.
+#[pyclass]
+#[derive(Clone)]
+pub struct TcpOptionFastOpen {
+    /// Fast Open cookie
+    #[pyo3(get, set)]
+    pub cookie: Vec<u8>,
+}
+
+#[pymethods]
+impl TcpOptionFastOpen {
+    /// Create a new option
+    ///
+    /// Args:
+    ///     cookie: Fast Open cookie (default: empty, a cookie request)
+    #[new]
+    #[pyo3(signature = (cookie = Vec::new()))]
+    pub fn new(cookie: Vec<u8>) -> Self {
+        Self { cookie }
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![FAST_OPEN, (self.cookie.len() + 2) as u8];
+        buf.extend_from_slice(&self.cookie);
+        buf
+    }
+}
+
+/// Multipath TCP option (kind 30, RFC 8684)
+///
+/// The option contents are kept as bytes; the subtype is the upper 4 bits
+/// of the first byte.
+#[pyclass]
+#[derive(Clone)]
+pub struct TcpOptionMptcp {
+    /// Option contents after the kind and length bytes
+    #[pyo3(get, set)]
+    pub data: Vec<u8>,
+}
+
+#[pymethods]
+impl TcpOptionMptcp {
+    /// Create a new option
+    ///
+    /// Args:
+    ///     data: Option contents after the kind and length bytes, starting
+    ///         with the subtype
+    #[new]
+    pub fn new(data: Vec<u8>) -> Self {
+        Self { data }
+    }
+
+    /// Get the MPTCP subtype (None if the option is empty)
+    #[getter]
+    pub fn subtype(&self) -> Option<u8> {
+        self.data.first().map(|byte| byte >> 4)
+    }
+
+    /// Get the name of the MPTCP subtype
+    ///
+    /// Returns "mp_capable", "mp_join", "dss", "add_addr", "remove_addr",
+    /// "mp_prio", "mp_fail", "mp_fastclose", "mp_tcprst" or None for other
+    /// subtypes.
+    #[getter]
+    pub fn subtype_name(&self) -> Option<&'static str> {
+        match self.subtype()? {
+            0 => Some("mp_capable"),
+            1 => Some("mp_join"),
+            2 => Some("dss"),
+            3 => Some("add_addr"),
+            4 => Some("remove_addr"),
+            5 => Some("mp_prio"),
+            6 => Some("mp_fail"),
+            7 => Some("mp_fastclose"),
+            8 => Some("mp_tcprst"),
+            _ => None,
+        }
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![MPTCP, (self.data.len() + 2) as u8];
+        buf.extend_from_slice(&self.data);
+        buf
+    }
+}
+
+/// Any other TCP option, kept as raw bytes
+#[pyclass]
+#[derive(Clone)]
+pub struct TcpOptionRaw {
+    /// Option kind byte
+    #[pyo3(get, set)]
+    pub kind: u8,
+
+    /// Option contents after the kind and length bytes
+    #[pyo3(get, set)]
+    pub data: Vec<u8>,
+}
+
+#[pymethods]
+impl TcpOptionRaw {
+    /// Create a new option
+    ///
+    /// Args:
+    ///     kind: Option kind byte
+    ///     data: Option contents after the kind and length bytes
+    #[new]
+    #[pyo3(signature = (kind, data = Vec::new()))]
+    pub fn new(kind: u8, data: Vec<u8>) -> Self {
+        Self { kind, data }
+    }
+
+    /// Serialize the option to bytes
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = vec![self.kind, (self.data.len() + 2) as u8];
+        buf.extend_from_slice(&self.data);
+        buf
+    }
+}
+
+/// One decoded TCP option, converted to and from the option classes
+#[derive(Clone, FromPyObject, IntoPyObject)]
+pub enum TcpOption {
+    Mss(TcpOptionMss),
+    WindowScale(TcpOptionWindowScale),
+    SackPermitted(TcpOptionSackPermitted),
+    Sack(TcpOptionSack),
+    Timestamp(TcpOptionTimestamp),
+    FastOpen(TcpOptionFastOpen),
+    Mptcp(TcpOptionMptcp),
+    Raw(TcpOptionRaw),
+}
+
+impl TcpOption {
+    fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Self::Mss(option) => option.to_bytes(),
+            Self::WindowScale(option) => option.to_bytes(),
+            Self::SackPermitted(option) => option.to_bytes(),
+            Self::Sack(option) => option.to_bytes(),
+            Self::Timestamp(option) => option.to_bytes(),
+            Self::FastOpen(option) => option.to_bytes(),
+            Self::Mptcp(option) => option.to_bytes(),
+            Self::Raw(option) => option.to_bytes(),
+        }
+    }
+}
+
+/// Decode a TCP options area
+pub(crate) fn decode_options(area: &[u8]) -> Vec<TcpOption> {
+    decode_option_area(area, decode_option, |kind, data| TcpOption::Raw(TcpOptionRaw::new(kind, data)))
+}
+
+/// Decode one option from its kind and the bytes after the length byte
+fn decode_option(kind: u8, data: &[u8]) -> TcpOption {
+    match (kind, data) {
+        (MAXIMUM_SEGMENT_SIZE, [high, low]) => {
+            TcpOption::Mss(TcpOptionMss::new(u16::from_be_bytes([*high, *low])))
+        },
+        (WINDOW_SCALE, [shift]) => TcpOption::WindowScale(TcpOptionWindowScale::new(*shift)),
+        (SACK_PERMITTED, []) => TcpOption::SackPermitted(TcpOptionSackPermitted::new()),
+        (SACK, blocks) if blocks.len().is_multiple_of(8) => {
+            let blocks = blocks
+                .chunks_exact(8)
+                .map(|block| (read_u32(&block[..4]), read_u32(&block[4..])))
+                .collect();
+            TcpOption::Sack(TcpOptionSack::new(blocks))
+        },
+        (TIMESTAMP, data) if data.len() == 8 => {
+            TcpOption::Timestamp(TcpOptionTimestamp::new(read_u32(&data[..4]), read_u32(&data[4..])))
+        },
+        (FAST_OPEN, cookie) => TcpOption::FastOpen(TcpOptionFastOpen::new(cookie.to_vec())),
+        (MPTCP, data) => TcpOption::Mptcp(TcpOptionMptcp::new(data.to_vec())),
+        _ => TcpOption::Raw(TcpOptionRaw::new(kind, data.to_vec())),
+    }
+}
+
+/// Encode options into an options area padded to a multiple of 4 bytes
+pub(crate) fn encode_options(options: &[TcpOption]) -> PyResult<Vec<u8>> {
+    encode_option_area("TCP", options.iter().map(TcpOption::to_bytes))
+}
+
+fn read_u32(bytes: &[u8]) -> u32 {
+    u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+}
@@ -1,4 +1,8 @@
 use pyo3::prelude::*;
+use crate::headers::{field_value, transport_checksum, IpHeader};
+
+/// IP protocol number of UDP, used in the pseudo header
+const UDP: u8 = 17;
 
 /// Python wrapper for etherparse::UdpHeader
 /// Represents a UDP header
@@ -7,6 +11,8 @@ use pyo3::prelude::*;
 pub struct UdpHeader {
     // Store the inner etherparse header
     inner: etherparse::UdpHeader,
+    // Checksum check made when the datagram was parsed
+    pub(crate) checksum_valid: Option<bool>,
 }
 
 #[pymethods]
@@ -17,7 +23,7 @@ impl UdpHeader {
     ///     source_port: Source port number
     ///     destination_port: Destination port number
     ///     length: Total UDP packet length (header + data) (default: calculated)
-    ///     checksum: UDP checksum (default: 0, will be calculated when serializing with payload)
+    ///     checksum: UDP checksum (default: 0, see calc_checksum_ipv4/calc_checksum_ipv6)
     #[new]
     #[pyo3(signature = (source_port, destination_port, length = 8, checksum = 0))]
     pub fn new(source_port: u16, destination_port: u16, length: u16, checksum: u16) -> Self {
@@ -29,7 +35,7 @@ impl UdpHeader {
             checksum,
         };
         
-        Self { inner: header }
+        Self { inner: header, checksum_valid: None }
     }
 
     /// Get the source port
@@ -56,16 +62,53 @@ impl UdpHeader {
         self.inner.checksum
     }
     
+    /// Check if the checksum was correct when the packet was parsed
+    ///
+    /// None for headers built in Python and for datagrams cut off by the
+    /// capture length.
+    #[getter]
+    pub fn checksum_valid(&self) -> Option<bool> {
+        self.checksum_valid
+    }
+    
+    /// Set the source port
+    ///
+    /// Raises:
+    ///     ValueError: If the port is out of range (0-65535)
+    #[setter]
+    pub fn set_source_port(&mut self, port: i64) -> PyResult<()> {
+        self.inner.source_port = field_value("Source port", port, u16::MAX.into())?;
+        Ok(())
+    }
+    
+    /// Set the destination port
+    ///
+    /// Raises:
+    ///     ValueError: If the port is out of range (0-65535)
+    #[setter]
+    pub fn set_destination_port(&mut self, port: i64) -> PyResult<()> {
+        self.inner.destination_port = field_value("Destination port", port, u16::MAX.into())?;
+        Ok(())
+    }
+    
     /// Set the length field (usually done automatically)
+    ///
+    /// Raises:
+    ///     ValueError: If the length doesn't fit into 16 bits
     #[setter]
-    pub fn set_length(&mut self, length: u16) {
-        self.inner.length = length;
+    pub fn set_length(&mut self, length: i64) -> PyResult<()> {
+        self.inner.length = field_value("Length", length, u16::MAX.into())?;
+        Ok(())
     }
     
     /// Set the checksum field (usually calculated automatically)
+    ///
+    /// Raises:
+    ///     ValueError: If the checksum doesn't fit into 16 bits
     #[setter]
-    pub fn set_checksum(&mut self, checksum: u16) {
-        self.inner.checksum = checksum;
+    pub fn set_checksum(&mut self, checksum: i64) -> PyResult<()> {
+        self.inner.checksum = field_value("Checksum", checksum, u16::MAX.into())?;
+        Ok(())
     }
 
     /// Get the header length (always 8 bytes for UDP)
@@ -79,11 +122,7 @@ impl UdpHeader {
     #[getter]
     pub fn payload_length(&self) -> u16 {
         // Payload length = total length - header length (8)
-        if self.inner.length > 8 {
-            self.inner.length - 8
-        } else {
-            0
-        }
+        self.inner.length.saturating_sub(8)
     }
 
     /// Serialize the UDP header to bytes
@@ -95,23 +134,81 @@ impl UdpHeader {
         self.inner.to_bytes().to_vec()
     }
     
-    /// Calculate checksum based on IPv4 pseudoheader and payload
+    /// Calculate and set the checksum using the IPv4 pseudo header
+    ///
+    /// The length field is updated to match the payload first.
     /// 
     /// Args:
     ///     source_ip: Source IPv4 address
     ///     dest_ip: Destination IPv4 address
     ///     payload: UDP payload data
-    pub fn calc_checksum_ipv4(&mut self, source_ip: [u8; 4], dest_ip: [u8; 4], payload: &[u8]) {
+    ///
+    /// Returns:
+    ///     int: The new checksum
+    ///
+    /// Raises:
+    ///     ValueError: If the payload is too large for a UDP datagram
+    pub fn calc_checksum_ipv4(&mut self, source_ip: [u8; 4], dest_ip: [u8; 4], payload: &[u8]) -> PyResult<u16> {
+        self.calc_checksum(&source_ip, &dest_ip, payload)
+    }
+    
+    /// Calculate and set the checksum using the IPv6 pseudo header
+    ///
+    /// The length field is updated to match the payload first. With a
+    /// routing header, pass the final destination as dest_ip.
+    /// 
+    /// Args:
+    ///     source_ip: Source IPv6 address
+    ///     dest_ip: Destination IPv6 address
+    ///     payload: UDP payload data
+    ///
+    /// Returns:
+    ///     int: The new checksum
+    ///
+    /// Raises:
+    ///     ValueError: If the payload is too large for a UDP datagram
+    pub fn calc_checksum_ipv6(&mut self, source_ip: [u8; 16], dest_ip: [u8; 16], payload: &[u8]) -> PyResult<u16> {
+        self.calc_checksum(&source_ip, &dest_ip, payload)
+    }
+    
+    /// Check the checksum against an IP header and the payload
+    ///
+    /// A zero checksum means "no checksum" and is accepted for IPv4 but not
+    /// for IPv6. Payload bytes beyond the length field (e.g. Ethernet
+    /// padding) are ignored.
+    ///
+    /// Args:
+    ///     ip: The Ipv4Header or Ipv6Header the datagram was sent with
+    ///     payload: UDP payload data
+    ///
+    /// Returns:
+    ///     bool: True if the checksum is correct
+    pub fn verify_checksum(&self, ip: IpHeader, payload: &[u8]) -> bool {
+        if self.inner.checksum == 0 {
+            return matches!(ip, IpHeader::V4(_));
+        }
+        let payload_len = payload.len().min(usize::from(self.inner.length).saturating_sub(8));
+        let (source, destination) = ip.pseudo_header_addresses();
+        transport_checksum(&source, &destination, UDP, &[&self.to_bytes(), &payload[..payload_len]]) == 0
+    }
+}
+
+impl UdpHeader {
+    fn calc_checksum(&mut self, source: &[u8], destination: &[u8], payload: &[u8]) -> PyResult<u16> {
         // Update the length field first to match header + payload
-        self.inner.length = 8 + payload.len() as u16;
+        self.inner.length = u16::try_from(8 + payload.len()).map_err(|_| {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "UDP payload of {} bytes is too large (at most 65527)",
+                payload.len()
+            ))
+        })?;
         
-        // etherparse 0.13.0'da checksum hesaplama değişti
-        // Şimdilik checksum'ı manuel olarak 0 yapıyoruz
-        // Gerçek implementasyon için etherparse documentation'ına bakılabilir
         self.inner.checksum = 0;
-        
-        // TODO: Implement proper UDP checksum calculation
-        // Bu işlem için etherparse'ın güncel API'sini kullanmak gerekiyor
+        let checksum = transport_checksum(source, destination, UDP, &[&self.to_bytes(), payload]);
+        // A computed checksum of zero is sent as all ones, since zero means
+        // "no checksum"
+        self.inner.checksum = if checksum == 0 { 0xffff } else { checksum };
+        Ok(self.inner.checksum)
     }
 }
 
@@ -119,7 +216,7 @@ impl UdpHeader {
 /// This is used when parsing packets
 impl From<etherparse::UdpHeader> for UdpHeader {
     fn from(header: etherparse::UdpHeader) -> Self {
-        Self { inner: header }
+        Self { inner: header, checksum_valid: None }
     }
 }
 
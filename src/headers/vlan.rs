@@ -0,0 +1,102 @@
+use pyo3::prelude::*;
+
+/// Python wrapper for etherparse::SingleVlanHeader
+/// Represents an IEEE 802.1Q VLAN tag
+#[pyclass]
+#[derive(Clone)]
+pub struct VlanHeader {
+    // Store the inner etherparse header
+    inner: etherparse::SingleVlanHeader,
+}
+
+#[pymethods]
+impl VlanHeader {
+    /// Create a new VLAN tag
+    ///
+    /// Args:
+    ///     vlan_id: 12-bit VLAN identifier (0-4095)
+    ///     ether_type: EtherType of the content after the tag (e.g. 0x0800 for IPv4)
+    ///     pcp: 3-bit priority code point (0-7) (default: 0)
+    ///     dei: Drop eligible indicator (default: False)
+    ///
+    /// Raises:
+    ///     ValueError: If the VLAN ID or priority code point is out of range
+    #[new]
+    #[pyo3(signature = (vlan_id, ether_type, pcp = 0, dei = false))]
+    pub fn new(vlan_id: u16, ether_type: u16, pcp: u8, dei: bool) -> PyResult<Self> {
+        if vlan_id > 0x0fff {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                format!("VLAN ID {} is out of range (0-4095)", vlan_id)
+            ));
+        }
+        if pcp > 7 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                format!("Priority code point {} is out of range (0-7)", pcp)
+            ));
+        }
+        Ok(Self {
+            inner: etherparse::SingleVlanHeader {
+                priority_code_point: pcp,
+                drop_eligible_indicator: dei,
+                vlan_identifier: vlan_id,
+                ether_type,
+            }
+        })
+    }
+
+    /// Get the priority code point (802.1p class of service, 0-7)
+    #[getter]
+    pub fn pcp(&self) -> u8 {
+        self.inner.priority_code_point
+    }
+
+    /// Get the drop eligible indicator
+    #[getter]
+    pub fn dei(&self) -> bool {
+        self.inner.drop_eligible_indicator
+    }
+
+    /// Get the VLAN identifier
+    #[getter]
+    pub fn vlan_id(&self) -> u16 {
+        self.inner.vlan_identifier
+    }
+
+    /// Get the EtherType of the content after the tag
+    #[getter]
+    pub fn ether_type(&self) -> u16 {
+        self.inner.ether_type
+    }
+
+    /// Serialize the VLAN tag to bytes
+    ///
+    /// Returns:
+    ///     A vector of 4 bytes representing the tag
+    pub fn to_bytes(&self) -> Vec<u8> {
+        // The constructor keeps all fields in range, so this can't fail
+        self.inner.to_bytes().unwrap().to_vec()
+    }
+}
+
+impl VlanHeader {
+    /// Set the EtherType of the content after the tag
+    pub(crate) fn set_ether_type(&mut self, ether_type: u16) {
+        self.inner.ether_type = ether_type;
+    }
+}
+
+/// Convert from etherparse::SingleVlanHeader to our VlanHeader
+/// This is used when parsing packets
+impl From<etherparse::SingleVlanHeader> for VlanHeader {
+    fn from(header: etherparse::SingleVlanHeader) -> Self {
+        Self { inner: header }
+    }
+}
+
+/// Convert from our VlanHeader to etherparse::SingleVlanHeader
+/// This is used when building packets
+impl From<&VlanHeader> for etherparse::SingleVlanHeader {
+    fn from(header: &VlanHeader) -> Self {
+        header.inner.clone()
+    }
+}
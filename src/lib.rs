@@ -1,20 +1,51 @@
 use pyo3::prelude::*;
 
 // Declare the modules
+mod flows;
 mod headers;
 mod models;
 mod parsers;
+mod reassembly;
+mod writers;
 
 // Use the types and functions from our modules
+use flows::{Flow, FlowTable};
 use headers::{
+    ArpHeader,
     Ethernet2Header,
+    Icmpv4Header,
+    Icmpv6Header,
     Ipv4Header,
+    Ipv4OptionRaw,
+    Ipv4OptionRecordRoute,
+    Ipv4OptionRouterAlert,
+    Ipv4OptionSecurity,
+    Ipv4OptionSourceRoute,
+    Ipv4OptionTimestamp,
     Ipv6Header,
+    Ipv6AuthenticationHeader,
+    Ipv6DestinationOptions,
+    Ipv6FragmentHeader,
+    Ipv6HopByHopOptions,
+    Ipv6RoutingHeader,
+    LinuxSllHeader,
+    NdpOption,
     TcpHeader,
+    TcpOptionFastOpen,
+    TcpOptionMptcp,
+    TcpOptionMss,
+    TcpOptionRaw,
+    TcpOptionSack,
+    TcpOptionSackPermitted,
+    TcpOptionTimestamp,
+    TcpOptionWindowScale,
     UdpHeader,
+    VlanHeader,
 };
-use models::ParsedPacket;
-use parsers::{parse_packet, parse_pcap_file};
+use models::{CaptureInterface, CaptureSection, CaptureStats, PacketBuilder, PacketError, ParsedPacket};
+use parsers::{parse_packet, parse_pcap_file, PcapReader};
+use reassembly::{Defragmenter, StreamChunk, TcpReassembler};
+use writers::{PcapNgWriter, PcapWriter};
 
 /// Python module for network packet parsing
 /// 
@@ -24,13 +55,59 @@ use parsers::{parse_packet, parse_pcap_file};
 fn pytherparse_native(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
     // Add header classes
     m.add_class::<Ethernet2Header>()?;
+    m.add_class::<LinuxSllHeader>()?;
     m.add_class::<Ipv4Header>()?;
+    m.add_class::<Ipv4OptionRecordRoute>()?;
+    m.add_class::<Ipv4OptionSourceRoute>()?;
+    m.add_class::<Ipv4OptionTimestamp>()?;
+    m.add_class::<Ipv4OptionRouterAlert>()?;
+    m.add_class::<Ipv4OptionSecurity>()?;
+    m.add_class::<Ipv4OptionRaw>()?;
     m.add_class::<Ipv6Header>()?;
+    m.add_class::<Ipv6HopByHopOptions>()?;
+    m.add_class::<Ipv6RoutingHeader>()?;
+    m.add_class::<Ipv6FragmentHeader>()?;
+    m.add_class::<Ipv6DestinationOptions>()?;
+    m.add_class::<Ipv6AuthenticationHeader>()?;
     m.add_class::<TcpHeader>()?;
+    m.add_class::<TcpOptionMss>()?;
+    m.add_class::<TcpOptionWindowScale>()?;
+    m.add_class::<TcpOptionSackPermitted>()?;
+    m.add_class::<TcpOptionSack>()?;
+    m.add_class::<TcpOptionTimestamp>()?;
+    m.add_class::<TcpOptionFastOpen>()?;
+    m.add_class::<TcpOptionMptcp>()?;
+    m.add_class::<TcpOptionRaw>()?;
     m.add_class::<UdpHeader>()?;
+    m.add_class::<Icmpv4Header>()?;
+    m.add_class::<Icmpv6Header>()?;
+    m.add_class::<NdpOption>()?;
+    m.add_class::<VlanHeader>()?;
+    m.add_class::<ArpHeader>()?;
     
-    // Add packet model class
+    // Add packet model classes
     m.add_class::<ParsedPacket>()?;
+    m.add_class::<PacketError>()?;
+    m.add_class::<PacketBuilder>()?;
+    
+    // Add streaming reader and capture metadata classes
+    m.add_class::<PcapReader>()?;
+    m.add_class::<CaptureInterface>()?;
+    m.add_class::<CaptureSection>()?;
+    m.add_class::<CaptureStats>()?;
+    
+    // Add capture writer classes
+    m.add_class::<PcapWriter>()?;
+    m.add_class::<PcapNgWriter>()?;
+    
+    // Add reassembly classes
+    m.add_class::<Defragmenter>()?;
+    m.add_class::<TcpReassembler>()?;
+    m.add_class::<StreamChunk>()?;
+    
+    // Add flow tracking classes
+    m.add_class::<FlowTable>()?;
+    m.add_class::<Flow>()?;
     
     // Add parsing functions
     m.add_function(wrap_pyfunction!(parse_packet, m)?)?;
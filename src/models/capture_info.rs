@@ -0,0 +1,115 @@
+use pyo3::prelude::*;
+
+/// Describes one capture interface of a pcapng file
+///
+/// Built from an Interface Description Block. Interface IDs are assigned
+/// in the order the blocks appear within their section.
+#[pyclass]
+#[derive(Clone)]
+pub struct CaptureInterface {
+    /// Index of the interface within its section
+    #[pyo3(get)]
+    pub interface_id: u32,
+
+    /// Link-layer header type (LINKTYPE_*) of packets captured on the interface
+    #[pyo3(get)]
+    pub link_type: u32,
+
+    /// Maximum number of bytes captured per packet (0 means unlimited)
+    #[pyo3(get)]
+    pub snaplen: u32,
+
+    /// Interface name (if_name option)
+    #[pyo3(get)]
+    pub name: Option<String>,
+
+    /// Interface description (if_description option)
+    #[pyo3(get)]
+    pub description: Option<String>,
+
+    /// Capture filter used on the interface (if_filter option)
+    #[pyo3(get)]
+    pub filter: Option<String>,
+
+    /// Operating system of the capturing machine (if_os option)
+    #[pyo3(get)]
+    pub os: Option<String>,
+
+    /// Number of timestamp units per second (if_tsresol option)
+    #[pyo3(get)]
+    pub timestamp_resolution: u64,
+
+    /// Seconds added to every timestamp of the interface (if_tsoffset option)
+    #[pyo3(get)]
+    pub timestamp_offset: i64,
+
+    /// Comments attached to the interface
+    #[pyo3(get)]
+    pub comments: Vec<String>,
+}
+
+/// Describes the section of a pcapng file currently being read
+///
+/// Built from a Section Header Block.
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct CaptureSection {
+    /// Major version of the pcapng format
+    #[pyo3(get)]
+    pub major_version: u16,
+
+    /// Minor version of the pcapng format
+    #[pyo3(get)]
+    pub minor_version: u16,
+
+    /// Hardware of the capturing machine (shb_hardware option)
+    #[pyo3(get)]
+    pub hardware: Option<String>,
+
+    /// Operating system of the capturing machine (shb_os option)
+    #[pyo3(get)]
+    pub os: Option<String>,
+
+    /// Application that wrote the section (shb_userappl option)
+    #[pyo3(get)]
+    pub user_application: Option<String>,
+
+    /// Comments attached to the section
+    #[pyo3(get)]
+    pub comments: Vec<String>,
+}
+
+/// Counts of the frames a reader has gone through so far
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct CaptureStats {
+    /// Number of frames read from the file
+    #[pyo3(get)]
+    pub frames_read: u64,
+
+    /// Number of frames that were decoded successfully
+    #[pyo3(get)]
+    pub packets_parsed: u64,
+
+    /// Number of frames that could not be decoded
+    #[pyo3(get)]
+    pub packets_failed: u64,
+
+    /// Error that stopped reading before the end of the file, if any
+    #[pyo3(get)]
+    pub read_error: Option<String>,
+}
+
+#[pymethods]
+impl CaptureStats {
+    pub fn __repr__(&self) -> String {
+        let read_error = match &self.read_error {
+            Some(error) => format!("{:?}", error),
+            None => "None".to_string(),
+        };
+        format!(
+            "CaptureStats(frames_read={}, packets_parsed={}, packets_failed={}, read_error={})",
+            self.frames_read, self.packets_parsed, self.packets_failed, read_error
+        )
+    }
+}
@@ -4,12 +4,13 @@
 //! and related components.
 
 // Declare submodules
+mod capture_info;
+mod packet_builder;
+mod packet_error;
 mod parsed_packet;
 
 // Re-export data structures
+pub use capture_info::{CaptureInterface, CaptureSection, CaptureStats};
+pub use packet_builder::PacketBuilder;
+pub use packet_error::PacketError;
 pub use parsed_packet::ParsedPacket;
-
-// Note: Additional model types can be added here in the future
-// For example:
-// mod packet_builder;
-// pub use packet_builder::PacketBuilder;
\ No newline at end of file
@@ -0,0 +1,319 @@
+use pyo3::prelude::*;
+use etherparse::{ether_type, ip_number};
+use crate::models::ParsedPacket;
+use crate::headers::{
+    ArpHeader, Ethernet2Header, Icmpv4Header, Icmpv6Header, Ipv4Header, Ipv6Header, LinuxSllHeader, TcpHeader,
+    UdpHeader, VlanHeader,
+};
+
+/// EtherTypes that mark an 802.1Q or 802.1ad VLAN tag
+const VLAN_TPIDS: [u16; 3] = [
+    ether_type::VLAN_TAGGED_FRAME,
+    ether_type::PROVIDER_BRIDGING,
+    ether_type::VLAN_DOUBLE_TAGGED_FRAME,
+];
+
+/// Builds a wire packet from headers and a payload
+///
+/// Layers are added with chainable methods and build() fills in every
+/// field that depends on the layers around it: EtherTypes, IP protocol
+/// numbers, IPv4 total length, IPv6 payload length, UDP length and the
+/// IPv4, TCP, UDP and ICMP checksums. All other fields are kept as given.
+///
+/// Example:
+///     frame = (
+///         PacketBuilder()
+///         .ethernet(Ethernet2Header(src_mac, dst_mac, 0))
+///         .ipv4(Ipv4Header(src_ip, dst_ip))
+///         .udp(UdpHeader(5353, 5353))
+///         .payload(b"hello")
+///         .build()
+///     )
+#[pyclass]
+#[derive(Clone, Default)]
+pub struct PacketBuilder {
+    link: Option<Ethernet2Header>,
+    linux_sll: Option<LinuxSllHeader>,
+    vlans: Vec<VlanHeader>,
+    arp: Option<ArpHeader>,
+    ipv4: Option<Ipv4Header>,
+    ipv6: Option<Ipv6Header>,
+    transport: Option<Transport>,
+    payload: Vec<u8>,
+}
+
+/// The transport layer of a packet being built
+#[derive(Clone)]
+enum Transport {
+    Tcp(TcpHeader),
+    Udp(UdpHeader),
+    Icmpv4(Icmpv4Header),
+    Icmpv6(Icmpv6Header),
+}
+
+#[pymethods]
+impl PacketBuilder {
+    /// Create an empty builder
+    #[new]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Use an Ethernet II link layer (replaces a Linux cooked capture header)
+    pub fn ethernet(mut slf: PyRefMut<'_, Self>, header: Ethernet2Header) -> PyRefMut<'_, Self> {
+        slf.link = Some(header);
+        slf.linux_sll = None;
+        slf
+    }
+
+    /// Use a Linux cooked capture link layer (replaces an Ethernet header)
+    pub fn linux_sll(mut slf: PyRefMut<'_, Self>, header: LinuxSllHeader) -> PyRefMut<'_, Self> {
+        slf.linux_sll = Some(header);
+        slf.link = None;
+        slf
+    }
+
+    /// Add a VLAN tag after the link layer
+    ///
+    /// Call twice for a double tagged (QinQ) frame, outer tag first.
+    ///
+    /// Raises:
+    ///     ValueError: If the frame already has two tags
+    pub fn vlan(mut slf: PyRefMut<'_, Self>, header: VlanHeader) -> PyResult<PyRefMut<'_, Self>> {
+        if slf.vlans.len() == 2 {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "A frame can have at most two VLAN tags",
+            ));
+        }
+        slf.vlans.push(header);
+        Ok(slf)
+    }
+
+    /// Use an ARP message as the network layer (replaces an IP header)
+    pub fn arp(mut slf: PyRefMut<'_, Self>, header: ArpHeader) -> PyRefMut<'_, Self> {
+        slf.arp = Some(header);
+        slf.ipv4 = None;
+        slf.ipv6 = None;
+        slf
+    }
+
+    /// Use an IPv4 network layer (replaces an IPv6 header or ARP message)
+    pub fn ipv4(mut slf: PyRefMut<'_, Self>, header: Ipv4Header) -> PyRefMut<'_, Self> {
+        slf.ipv4 = Some(header);
+        slf.ipv6 = None;
+        slf.arp = None;
+        slf
+    }
+
+    /// Use an IPv6 network layer (replaces an IPv4 header or ARP message)
+    pub fn ipv6(mut slf: PyRefMut<'_, Self>, header: Ipv6Header) -> PyRefMut<'_, Self> {
+        slf.ipv6 = Some(header);
+        slf.ipv4 = None;
+        slf.arp = None;
+        slf
+    }
+
+    /// Use a TCP transport layer (replaces any other transport header)
+    pub fn tcp(mut slf: PyRefMut<'_, Self>, header: TcpHeader) -> PyRefMut<'_, Self> {
+        slf.transport = Some(Transport::Tcp(header));
+        slf
+    }
+
+    /// Use a UDP transport layer (replaces any other transport header)
+    pub fn udp(mut slf: PyRefMut<'_, Self>, header: UdpHeader) -> PyRefMut<'_, Self> {
+        slf.transport = Some(Transport::Udp(header));
+        slf
+    }
+
+    /// Use an ICMPv4 message (replaces any transport header)
+    pub fn icmpv4(mut slf: PyRefMut<'_, Self>, header: Icmpv4Header) -> PyRefMut<'_, Self> {
+        slf.transport = Some(Transport::Icmpv4(header));
+        slf
+    }
+
+    /// Use an ICMPv6 message (replaces any transport header)
+    pub fn icmpv6(mut slf: PyRefMut<'_, Self>, header: Icmpv6Header) -> PyRefMut<'_, Self> {
+        slf.transport = Some(Transport::Icmpv6(header));
+        slf
+    }
+
+    /// Set the payload after the last header
+    pub fn payload(mut slf: PyRefMut<'_, Self>, data: Vec<u8>) -> PyRefMut<'_, Self> {
+        slf.payload = data;
+        slf
+    }
+
+    /// Build the packet
+    ///
+    /// The builder itself is left unchanged, so it can be modified and
+    /// built again.
+    ///
+    /// Returns:
+    ///     bytes: The packet with lengths and checksums filled in
+    ///
+    /// Raises:
+    ///     ValueError: If the layers don't fit together (e.g. a transport
+    ///         header after an ARP message) or the packet is too large for
+    ///         its length fields
+    pub fn build(&self) -> PyResult<Vec<u8>> {
+        let mut packet = self.clone();
+        packet.fix_up()?;
+        Ok(packet.serialize())
+    }
+}
+
+impl PacketBuilder {
+    /// Create a builder holding the layers of a parsed packet
+    ///
+    /// Bytes after the IP packet (see ParsedPacket.to_bytes) are left out
+    /// of the payload.
+    pub(crate) fn from_packet(packet: &ParsedPacket) -> Self {
+        let transport = if let Some(tcp) = &packet.tcp {
+            Some(Transport::Tcp(tcp.clone()))
+        } else if let Some(udp) = &packet.udp {
+            Some(Transport::Udp(udp.clone()))
+        } else if let Some(icmpv4) = &packet.icmpv4 {
+            Some(Transport::Icmpv4(icmpv4.clone()))
+        } else {
+            packet.icmpv6.clone().map(Transport::Icmpv6)
+        };
+        Self {
+            link: packet.link.clone(),
+            linux_sll: packet.linux_sll.clone(),
+            vlans: packet.vlan.iter().chain(&packet.vlan_inner).cloned().collect(),
+            arp: packet.arp.clone(),
+            ipv4: packet.ipv4.clone(),
+            ipv6: packet.ipv6.clone(),
+            transport,
+            payload: packet.ip_payload().to_vec(),
+        }
+    }
+
+    /// Fill in the fields that depend on other layers, innermost first
+    fn fix_up(&mut self) -> PyResult<()> {
+        if self.arp.is_some() && self.transport.is_some() {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "An ARP message can't be followed by a transport header",
+            ));
+        }
+
+        // Transport lengths and checksums over the pseudo header
+        let payload = self.payload.as_slice();
+        let ipv4 = self.ipv4.as_ref().map(|ip| (ip.source(), ip.destination()));
+        let ipv6 = self.ipv6.as_ref().map(|ip| (ip.source(), ip.final_destination()));
+        let mut transport_len = 0;
+        let mut protocol = None;
+        if let Some(transport) = &mut self.transport {
+            match transport {
+                Transport::Tcp(tcp) => {
+                    if let Some((source, destination)) = ipv4 {
+                        tcp.calc_checksum_ipv4(source, destination, payload);
+                    } else if let Some((source, destination)) = ipv6 {
+                        tcp.calc_checksum_ipv6(source, destination, payload);
+                    }
+                    transport_len = tcp.to_bytes().len();
+                    protocol = Some(ip_number::TCP);
+                },
+                Transport::Udp(udp) => {
+                    if let Some((source, destination)) = ipv4 {
+                        udp.calc_checksum_ipv4(source, destination, payload)?;
+                    } else if let Some((source, destination)) = ipv6 {
+                        udp.calc_checksum_ipv6(source, destination, payload)?;
+                    } else {
+                        udp.set_length(8 + payload.len() as i64)?;
+                    }
+                    transport_len = 8;
+                    protocol = Some(ip_number::UDP);
+                },
+                Transport::Icmpv4(icmpv4) => {
+                    icmpv4.calc_checksum(payload.to_vec());
+                    transport_len = icmpv4.to_bytes().len();
+                    protocol = Some(ip_number::ICMP);
+                },
+                Transport::Icmpv6(icmpv6) => {
+                    if let Some((source, destination)) = ipv6 {
+                        icmpv6.calc_checksum(source, destination, payload.to_vec());
+                    }
+                    transport_len = icmpv6.to_bytes().len();
+                    protocol = Some(ip_number::IPV6_ICMP);
+                },
+            }
+        }
+
+        // IP protocol and lengths; the IPv4 header checksum is calculated
+        // when the header is serialized
+        let ip_payload_len = (transport_len + payload.len()) as i64;
+        let mut network_type = None;
+        if let Some(ipv4) = &mut self.ipv4 {
+            if let Some(protocol) = protocol {
+                ipv4.set_protocol(protocol.into())?;
+            }
+            ipv4.set_payload_length(ip_payload_len)?;
+            network_type = Some(ether_type::IPV4);
+        }
+        if let Some(ipv6) = &mut self.ipv6 {
+            if let Some(protocol) = protocol {
+                ipv6.set_upper_layer_protocol(protocol.into())?;
+            }
+            let extensions_len = ipv6.header_length() as i64 - 40;
+            ipv6.set_payload_length(extensions_len + ip_payload_len)?;
+            network_type = Some(ether_type::IPV6);
+        }
+        if self.arp.is_some() {
+            network_type = Some(ether_type::ARP);
+        }
+
+        // EtherTypes from the link layer through the VLAN tags
+        let mut next_type = network_type;
+        for i in (0..self.vlans.len()).rev() {
+            if let Some(next_type) = next_type {
+                self.vlans[i].set_ether_type(next_type);
+            }
+            next_type = Some(ether_type::VLAN_TAGGED_FRAME);
+        }
+        if let (Some(link), Some(next_type)) = (&mut self.link, next_type) {
+            // Keep a VLAN tag protocol ID the caller chose, e.g. 0x88a8
+            let keep = !self.vlans.is_empty() && VLAN_TPIDS.contains(&link.ether_type());
+            if !keep {
+                let next_type = if self.vlans.len() == 2 { ether_type::PROVIDER_BRIDGING } else { next_type };
+                link.set_ether_type(next_type.into())?;
+            }
+        }
+        if let (Some(linux_sll), Some(next_type)) = (&mut self.linux_sll, next_type) {
+            linux_sll.set_protocol(next_type);
+        }
+        Ok(())
+    }
+
+    /// Concatenate the headers and the payload
+    fn serialize(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        if let Some(link) = &self.link {
+            bytes.extend(link.to_bytes());
+        }
+        if let Some(linux_sll) = &self.linux_sll {
+            bytes.extend(linux_sll.to_bytes());
+        }
+        for vlan in &self.vlans {
+            bytes.extend(vlan.to_bytes());
+        }
+        if let Some(arp) = &self.arp {
+            bytes.extend(arp.to_bytes());
+        }
+        if let Some(ipv4) = &self.ipv4 {
+            bytes.extend(ipv4.to_bytes(true));
+        }
+        if let Some(ipv6) = &self.ipv6 {
+            bytes.extend(ipv6.to_bytes());
+        }
+        match &self.transport {
+            Some(Transport::Tcp(tcp)) => bytes.extend(tcp.to_bytes()),
+            Some(Transport::Udp(udp)) => bytes.extend(udp.to_bytes()),
+            Some(Transport::Icmpv4(icmpv4)) => bytes.extend(icmpv4.to_bytes()),
+            Some(Transport::Icmpv6(icmpv6)) => bytes.extend(icmpv6.to_bytes()),
+            None => {},
+        }
+        bytes.extend_from_slice(&self.payload);
+        bytes
+    }
+}
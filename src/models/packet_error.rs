@@ -0,0 +1,55 @@
+use std::fmt;
+use pyo3::prelude::*;
+
+/// Describes why a captured frame could not be fully decoded
+///
+/// The layer is the protocol whose header failed to decode, such as
+/// "ethernet", "linux_sll", "vlan", "ipv4" or "tcp". "ip" means the IP
+/// version could not be determined and "link" that the link type is not
+/// supported. Errors reading the capture file itself use the layer
+/// "capture".
+#[pyclass]
+#[derive(Clone)]
+pub struct PacketError {
+    /// Protocol layer the error occurred in
+    #[pyo3(get)]
+    pub layer: String,
+
+    /// Byte offset within the frame where the failing header starts
+    #[pyo3(get)]
+    pub offset: usize,
+
+    /// Description of the error
+    #[pyo3(get)]
+    pub message: String,
+}
+
+#[pymethods]
+impl PacketError {
+    pub fn __str__(&self) -> String {
+        self.to_string()
+    }
+
+    pub fn __repr__(&self) -> String {
+        format!(
+            "PacketError(layer={:?}, offset={}, message={:?})",
+            self.layer, self.offset, self.message
+        )
+    }
+}
+
+impl PacketError {
+    pub(crate) fn new(layer: &str, offset: usize, message: impl Into<String>) -> Self {
+        Self {
+            layer: layer.to_string(),
+            offset,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for PacketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} header at offset {}: {}", self.layer, self.offset, self.message)
+    }
+}
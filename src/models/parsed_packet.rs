@@ -1,5 +1,10 @@
 use pyo3::prelude::*;
-use crate::headers::{Ethernet2Header, Ipv4Header, Ipv6Header, TcpHeader, UdpHeader};
+use pyo3::types::{timezone_utc, PyDateTime, PyDelta};
+use crate::headers::{
+    ArpHeader, Ethernet2Header, Icmpv4Header, Icmpv6Header, Ipv4Header, Ipv6Header, LinuxSllHeader, TcpHeader,
+    UdpHeader, VlanHeader,
+};
+use crate::models::{PacketBuilder, PacketError};
 
 /// Represents a parsed network packet with various header components
 /// 
@@ -13,6 +18,22 @@ pub struct ParsedPacket {
     #[pyo3(get)]
     pub link: Option<Ethernet2Header>,
     
+    /// Linux cooked capture header (LINKTYPE_LINUX_SLL/SLL2 captures)
+    #[pyo3(get)]
+    pub linux_sll: Option<LinuxSllHeader>,
+    
+    /// 802.1Q VLAN tag (the outer tag of double tagged frames)
+    #[pyo3(get)]
+    pub vlan: Option<VlanHeader>,
+    
+    /// Inner VLAN tag of double tagged (QinQ) frames
+    #[pyo3(get)]
+    pub vlan_inner: Option<VlanHeader>,
+    
+    /// ARP message (if present)
+    #[pyo3(get)]
+    pub arp: Option<ArpHeader>,
+    
     /// IPv4 header (if present)
     #[pyo3(get)]
     pub ipv4: Option<Ipv4Header>,
@@ -29,9 +50,84 @@ pub struct ParsedPacket {
     #[pyo3(get)]
     pub udp: Option<UdpHeader>,
     
+    /// ICMPv4 header (if present)
+    #[pyo3(get)]
+    pub icmpv4: Option<Icmpv4Header>,
+    
+    /// ICMPv6 header (if present)
+    #[pyo3(get)]
+    pub icmpv6: Option<Icmpv6Header>,
+    
+    // Original datagram quoted by an ICMP error message, decoded
+    pub(crate) icmp_original: Option<Box<ParsedPacket>>,
+    
     /// Payload data (application layer content)
     #[pyo3(get)]
     pub payload: Vec<u8>,
+    
+    /// Capture timestamp, whole seconds since the Unix epoch
+    #[pyo3(get)]
+    pub ts_sec: i64,
+    
+    /// Capture timestamp, nanoseconds within the second
+    #[pyo3(get)]
+    pub ts_nsec: u32,
+    
+    /// Number of bytes of the frame that were captured
+    #[pyo3(get)]
+    pub captured_length: u32,
+    
+    /// Length of the frame on the wire (larger than captured_length if truncated)
+    #[pyo3(get)]
+    pub original_length: u32,
+    
+    /// Zero-based index of the frame within its capture file
+    #[pyo3(get)]
+    pub frame_index: u64,
+    
+    /// Link-layer header type (LINKTYPE_*) of the frame, if known
+    #[pyo3(get)]
+    pub link_type: Option<u32>,
+    
+    /// ID of the pcapng interface the frame was captured on
+    #[pyo3(get)]
+    pub interface_id: Option<u32>,
+    
+    /// Name of the pcapng interface the frame was captured on
+    #[pyo3(get)]
+    pub interface_name: Option<String>,
+    
+    /// Comments attached to the frame in a pcapng file
+    #[pyo3(get)]
+    pub comments: Vec<String>,
+    
+    /// Raw pcapng epb_flags option (direction, reception type, FCS length, errors)
+    #[pyo3(get)]
+    pub packet_flags: Option<u32>,
+    
+    /// Why the frame could not be fully decoded (None for packets that parsed)
+    #[pyo3(get)]
+    pub error: Option<PacketError>,
+    
+    /// Captured bytes of the frame the packet was parsed from (empty for
+    /// packets created from Python)
+    #[pyo3(get)]
+    pub raw: Vec<u8>,
+    
+    // Name, offset and length of each decoded layer within raw
+    pub(crate) layer_spans: Vec<(&'static str, usize, usize)>,
+    
+    // Whether a header or the payload was replaced since parsing, in which
+    // case raw no longer matches the layers
+    pub(crate) edited: bool,
+    
+    // Link-layer header bytes without a header class (BSD null, loopback
+    // and PPP headers), kept so the frame can be serialized again
+    pub(crate) link_header: Vec<u8>,
+    
+    // Number of bytes at the end of the payload that follow the IP packet,
+    // such as Ethernet padding
+    pub(crate) trailer_len: usize,
 }
 
 #[pymethods]
@@ -41,14 +137,47 @@ impl ParsedPacket {
     pub fn new() -> Self {
         Self {
             link: None,
+            linux_sll: None,
+            vlan: None,
+            vlan_inner: None,
+            arp: None,
             ipv4: None,
             ipv6: None,
             tcp: None,
             udp: None,
+            icmpv4: None,
+            icmpv6: None,
+            icmp_original: None,
             payload: Vec::new(),
+            ts_sec: 0,
+            ts_nsec: 0,
+            captured_length: 0,
+            original_length: 0,
+            frame_index: 0,
+            link_type: None,
+            interface_id: None,
+            interface_name: None,
+            comments: Vec::new(),
+            packet_flags: None,
+            error: None,
+            raw: Vec::new(),
+            layer_spans: Vec::new(),
+            edited: false,
+            link_header: Vec::new(),
+            trailer_len: 0,
         }
     }
     
+    /// Check if the packet carries a VLAN tag
+    pub fn has_vlan(&self) -> bool {
+        self.vlan.is_some()
+    }
+    
+    /// Check if the packet contains an ARP message
+    pub fn has_arp(&self) -> bool {
+        self.arp.is_some()
+    }
+    
     /// Check if the packet contains an IPv4 header
     pub fn has_ipv4(&self) -> bool {
         self.ipv4.is_some()
@@ -69,6 +198,21 @@ impl ParsedPacket {
         self.udp.is_some()
     }
     
+    /// Check if decoding the packet failed (see the error attribute)
+    pub fn has_error(&self) -> bool {
+        self.error.is_some()
+    }
+    
+    /// Check if the packet contains an ICMPv4 header
+    pub fn has_icmpv4(&self) -> bool {
+        self.icmpv4.is_some()
+    }
+    
+    /// Check if the packet contains an ICMPv6 header
+    pub fn has_icmpv6(&self) -> bool {
+        self.icmpv6.is_some()
+    }
+    
     /// Get the IP version of the packet (4, 6, or 0 if not an IP packet)
     pub fn ip_version(&self) -> u8 {
         if self.ipv4.is_some() {
@@ -80,6 +224,99 @@ impl ParsedPacket {
         }
     }
     
+    // Header setters
+    //
+    // Headers returned by the getters are copies, so edited headers have to
+    // be assigned back. Doing so marks the packet as edited, and writers
+    // then serialize the headers instead of the captured frame.
+    
+    /// Replace the link layer header (None removes it)
+    #[setter]
+    pub fn set_link(&mut self, link: Option<Ethernet2Header>) {
+        self.link = link;
+        self.edited = true;
+    }
+    
+    /// Replace the Linux cooked capture header (None removes it)
+    #[setter]
+    pub fn set_linux_sll(&mut self, linux_sll: Option<LinuxSllHeader>) {
+        self.linux_sll = linux_sll;
+        self.edited = true;
+    }
+    
+    /// Replace the outer VLAN tag (None removes it)
+    #[setter]
+    pub fn set_vlan(&mut self, vlan: Option<VlanHeader>) {
+        self.vlan = vlan;
+        self.edited = true;
+    }
+    
+    /// Replace the inner VLAN tag (None removes it)
+    #[setter]
+    pub fn set_vlan_inner(&mut self, vlan_inner: Option<VlanHeader>) {
+        self.vlan_inner = vlan_inner;
+        self.edited = true;
+    }
+    
+    /// Replace the ARP message (None removes it)
+    #[setter]
+    pub fn set_arp(&mut self, arp: Option<ArpHeader>) {
+        self.arp = arp;
+        self.edited = true;
+    }
+    
+    /// Replace the IPv4 header (None removes it)
+    #[setter]
+    pub fn set_ipv4(&mut self, ipv4: Option<Ipv4Header>) {
+        self.ipv4 = ipv4;
+        self.edited = true;
+    }
+    
+    /// Replace the IPv6 header (None removes it)
+    #[setter]
+    pub fn set_ipv6(&mut self, ipv6: Option<Ipv6Header>) {
+        self.ipv6 = ipv6;
+        self.edited = true;
+    }
+    
+    /// Replace the TCP header (None removes it)
+    #[setter]
+    pub fn set_tcp(&mut self, tcp: Option<TcpHeader>) {
+        self.tcp = tcp;
+        self.edited = true;
+    }
+    
+    /// Replace the UDP header (None removes it)
+    #[setter]
+    pub fn set_udp(&mut self, udp: Option<UdpHeader>) {
+        self.udp = udp;
+        self.edited = true;
+    }
+    
+    /// Replace the ICMPv4 header (None removes it)
+    #[setter]
+    pub fn set_icmpv4(&mut self, icmpv4: Option<Icmpv4Header>) {
+        self.icmpv4 = icmpv4;
+        self.edited = true;
+    }
+    
+    /// Replace the ICMPv6 header (None removes it)
+    #[setter]
+    pub fn set_icmpv6(&mut self, icmpv6: Option<Icmpv6Header>) {
+        self.icmpv6 = icmpv6;
+        self.edited = true;
+    }
+    
+    /// Get the original datagram quoted by an ICMP error message, decoded
+    /// when the packet was parsed (None for other packets)
+    ///
+    /// The quote is usually cut off after 8 bytes of the transport header,
+    /// so the returned packet may carry an error for that layer.
+    #[getter]
+    pub fn icmp_original(&self) -> Option<ParsedPacket> {
+        self.icmp_original.as_deref().cloned()
+    }
+    
     /// Get the payload length
     pub fn payload_length(&self) -> usize {
         self.payload.len()
@@ -93,5 +330,214 @@ impl ParsedPacket {
     /// Set or replace the payload
     pub fn set_payload(&mut self, data: Vec<u8>) {
         self.payload = data;
+        self.trailer_len = 0;
+        self.edited = true;
+    }
+    
+    /// Serialize the packet back to wire bytes
+    ///
+    /// With recompute, the frame is rebuilt from its layers like
+    /// PacketBuilder.build() does: EtherTypes, protocol numbers, lengths and
+    /// checksums are filled in, while Ethernet padding is kept as it was.
+    /// A truncated capture is rebuilt as a shorter, complete packet.
+    ///
+    /// Without recompute, parsed packets that weren't edited come back
+    /// exactly as captured; other packets are serialized with every field
+    /// as it is.
+    ///
+    /// Args:
+    ///     recompute: Recalculate lengths and checksums (default: True)
+    ///
+    /// Returns:
+    ///     bytes: The serialized frame
+    ///
+    /// Raises:
+    ///     ValueError: If recomputing fails because the layers don't fit
+    ///         together or are too large for their length fields
+    #[pyo3(signature = (recompute = true))]
+    pub fn to_bytes(&self, recompute: bool) -> PyResult<Vec<u8>> {
+        if !recompute {
+            return Ok(match self.captured() {
+                Some(raw) => raw.to_vec(),
+                None => self.serialize(false),
+            });
+        }
+        
+        let mut bytes = self.link_header.clone();
+        bytes.extend(PacketBuilder::from_packet(self).build()?);
+        bytes.extend_from_slice(self.trailer());
+        Ok(bytes)
+    }
+    
+    /// Get the byte range a layer occupies in the captured frame
+    ///
+    /// Layers are named like the packet attributes ("link", "linux_sll",
+    /// "vlan", "vlan_inner", "arp", "ipv4", "ipv6", "tcp", "udp", "icmpv4",
+    /// "icmpv6" and "payload"); the null, loopback and PPP headers of other
+    /// link types are "null", "loop" and "ppp". IPv4 and IPv6 spans include
+    /// options and extension headers.
+    ///
+    /// Spans refer to raw, so they describe the packet as it was captured
+    /// even after its headers were edited.
+    ///
+    /// Args:
+    ///     layer: Name of the layer
+    ///
+    /// Returns:
+    ///     tuple[int, int] | None: Offset and length of the layer, or None if
+    ///         the frame doesn't contain it
+    pub fn layer_span(&self, layer: &str) -> Option<(usize, usize)> {
+        self.layer_spans
+            .iter()
+            .find(|(name, _, _)| *name == layer)
+            .map(|(_, offset, length)| (*offset, *length))
+    }
+    
+    /// Get the byte ranges of all decoded layers, in frame order
+    ///
+    /// Returns:
+    ///     list[tuple[str, int, int]]: Name, offset and length of each layer
+    ///         (see layer_span)
+    #[getter]
+    pub fn layer_spans(&self) -> Vec<(&'static str, usize, usize)> {
+        self.layer_spans.clone()
+    }
+    
+    /// Get the microseconds part of the capture timestamp
+    #[getter]
+    pub fn ts_usec(&self) -> u32 {
+        self.ts_nsec / 1000
+    }
+    
+    /// Get the capture timestamp as seconds since the Unix epoch
+    #[getter]
+    pub fn timestamp(&self) -> f64 {
+        self.ts_sec as f64 + f64::from(self.ts_nsec) / 1e9
+    }
+    
+    /// Get the capture timestamp as a timezone-aware UTC datetime
+    ///
+    /// The datetime type only has microsecond resolution; use ts_nsec for
+    /// the full precision of nanosecond captures.
+    #[getter]
+    pub fn datetime<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        // Build from whole seconds and add the fraction separately so the
+        // microseconds don't suffer from float rounding
+        let utc = timezone_utc(py);
+        let seconds = PyDateTime::from_timestamp(py, self.ts_sec as f64, Some(&utc))?;
+        let fraction = PyDelta::new(py, 0, 0, self.ts_usec() as i32, false)?;
+        seconds.add(fraction)
+    }
+    
+    /// Get the direction from the pcapng packet flags
+    ///
+    /// Returns "inbound", "outbound" or None if unknown.
+    #[getter]
+    pub fn direction(&self) -> Option<&'static str> {
+        match self.packet_flags? & 0b11 {
+            0b01 => Some("inbound"),
+            0b10 => Some("outbound"),
+            _ => None,
+        }
+    }
+}
+
+impl ParsedPacket {
+    /// Create an empty packet for the given captured frame
+    ///
+    /// The original length defaults to the captured length until capture
+    /// metadata says otherwise.
+    pub(crate) fn from_frame(data: &[u8]) -> Self {
+        let mut packet = Self::new();
+        packet.captured_length = data.len() as u32;
+        packet.original_length = data.len() as u32;
+        packet.raw = data.to_vec();
+        packet
+    }
+    
+    /// Attach the capture record metadata of the frame this packet was parsed from
+    pub(crate) fn set_capture_info(
+        &mut self,
+        ts_sec: i64,
+        ts_nsec: u32,
+        original_length: u32,
+        frame_index: u64,
+    ) {
+        self.ts_sec = ts_sec;
+        self.ts_nsec = ts_nsec;
+        self.original_length = original_length;
+        self.frame_index = frame_index;
+    }
+    
+    /// Get the bytes of the frame as they would appear on the wire
+    ///
+    /// Returns the captured frame for parsed packets that weren't edited;
+    /// otherwise the headers are serialized one after another, followed by
+    /// the payload.
+    pub(crate) fn wire_bytes(&self) -> Vec<u8> {
+        match self.captured() {
+            Some(raw) => raw.to_vec(),
+            None => self.serialize(true),
+        }
+    }
+    
+    /// Get the captured frame if the layers still match it
+    fn captured(&self) -> Option<&[u8]> {
+        if self.edited || self.raw.is_empty() {
+            return None;
+        }
+        Some(&self.raw)
+    }
+    
+    /// Get the payload bytes that belong to the IP packet, without the trailer
+    pub(crate) fn ip_payload(&self) -> &[u8] {
+        &self.payload[..self.payload.len() - self.trailer_len]
+    }
+    
+    /// Get the bytes following the IP packet, such as Ethernet padding
+    fn trailer(&self) -> &[u8] {
+        &self.payload[self.payload.len() - self.trailer_len..]
+    }
+    
+    /// Concatenate the headers and the payload with their fields as they are
+    ///
+    /// Only the IPv4 header checksum can optionally be recalculated.
+    fn serialize(&self, recompute_ipv4_checksum: bool) -> Vec<u8> {
+        let mut bytes = self.link_header.clone();
+        if let Some(link) = &self.link {
+            bytes.extend(link.to_bytes());
+        }
+        if let Some(linux_sll) = &self.linux_sll {
+            bytes.extend(linux_sll.to_bytes());
+        }
+        if let Some(vlan) = &self.vlan {
+            bytes.extend(vlan.to_bytes());
+        }
+        if let Some(vlan_inner) = &self.vlan_inner {
+            bytes.extend(vlan_inner.to_bytes());
+        }
+        if let Some(arp) = &self.arp {
+            bytes.extend(arp.to_bytes());
+        }
+        if let Some(ipv4) = &self.ipv4 {
+            bytes.extend(ipv4.to_bytes(recompute_ipv4_checksum));
+        }
+        if let Some(ipv6) = &self.ipv6 {
+            bytes.extend(ipv6.to_bytes());
+        }
+        if let Some(tcp) = &self.tcp {
+            bytes.extend(tcp.to_bytes());
+        }
+        if let Some(udp) = &self.udp {
+            bytes.extend(udp.to_bytes());
+        }
+        if let Some(icmpv4) = &self.icmpv4 {
+            bytes.extend(icmpv4.to_bytes());
+        }
+        if let Some(icmpv6) = &self.icmpv6 {
+            bytes.extend(icmpv6.to_bytes());
+        }
+        bytes.extend_from_slice(&self.payload);
+        bytes
     }
 }
\ No newline at end of file
@@ -0,0 +1,143 @@
+//! Capture file sources
+//!
+//! This module hides which backend reads a capture file. By default the
+//! built-in pure-Rust readers are used, picking pcap or pcapng from the
+//! file's magic number; building with the `libpcap` feature switches to
+//! libpcap instead.
+
+use std::collections::HashMap;
+use std::io;
+use crate::models::{CaptureInterface, CaptureSection};
+
+#[cfg(not(feature = "libpcap"))]
+use std::fs::File;
+#[cfg(not(feature = "libpcap"))]
+use std::io::{BufRead, BufReader};
+#[cfg(not(feature = "libpcap"))]
+use crate::parsers::pcap_file::PcapFileReader;
+#[cfg(not(feature = "libpcap"))]
+use crate::parsers::pcapng::{PcapNgReader, SECTION_HEADER_BLOCK};
+#[cfg(feature = "libpcap")]
+use crate::parsers::libpcap::LibpcapReader;
+
+/// One packet record read from a capture file
+pub(crate) struct CaptureRecord {
+    /// Capture timestamp, whole seconds since the Unix epoch
+    pub ts_sec: i64,
+    /// Capture timestamp, nanoseconds within the second
+    pub ts_nsec: u32,
+    /// Length of the frame on the wire
+    pub original_length: u32,
+    /// Link-layer header type (LINKTYPE_*) of the frame
+    pub link_type: u32,
+    /// ID of the capture interface (pcapng only)
+    pub interface_id: Option<u32>,
+    /// Name of the capture interface (pcapng only)
+    pub interface_name: Option<String>,
+    /// Comments attached to the packet (pcapng only)
+    pub comments: Vec<String>,
+    /// Link-layer flags of the packet (pcapng epb_flags option)
+    pub flags: Option<u32>,
+    /// Captured bytes of the frame
+    pub data: Vec<u8>,
+}
+
+/// An open capture file, read through whichever backend is compiled in
+pub(crate) enum CaptureSource {
+    #[cfg(not(feature = "libpcap"))]
+    Pcap(PcapFileReader<BufReader<File>>),
+    #[cfg(not(feature = "libpcap"))]
+    PcapNg(PcapNgReader<BufReader<File>>),
+    #[cfg(feature = "libpcap")]
+    Libpcap(LibpcapReader),
+}
+
+impl CaptureSource {
+    /// Open a capture file
+    ///
+    /// Fails with `InvalidData` if the file isn't in a supported format.
+    #[cfg(not(feature = "libpcap"))]
+    pub fn open(path: &str) -> io::Result<Self> {
+        let mut file = BufReader::new(File::open(path)?);
+
+        // Peek at the magic number to tell pcapng from classic pcap
+        let is_pcapng = file.fill_buf()?.starts_with(&SECTION_HEADER_BLOCK.to_le_bytes());
+        if is_pcapng {
+            Ok(CaptureSource::PcapNg(PcapNgReader::new(file)?))
+        } else {
+            Ok(CaptureSource::Pcap(PcapFileReader::new(file)?))
+        }
+    }
+
+    /// Open a capture file
+    #[cfg(feature = "libpcap")]
+    pub fn open(path: &str) -> io::Result<Self> {
+        Ok(CaptureSource::Libpcap(LibpcapReader::open(path)?))
+    }
+
+    /// Read the next record, or `None` at the end of the file
+    pub fn next_record(&mut self) -> io::Result<Option<CaptureRecord>> {
+        match self {
+            #[cfg(not(feature = "libpcap"))]
+            CaptureSource::Pcap(reader) => reader.next_record(),
+            #[cfg(not(feature = "libpcap"))]
+            CaptureSource::PcapNg(reader) => reader.next_record(),
+            #[cfg(feature = "libpcap")]
+            CaptureSource::Libpcap(reader) => reader.next_record(),
+        }
+    }
+
+    /// Link-layer header type (LINKTYPE_*) of the file
+    pub fn link_type(&self) -> u32 {
+        match self {
+            #[cfg(not(feature = "libpcap"))]
+            CaptureSource::Pcap(reader) => reader.link_type(),
+            #[cfg(not(feature = "libpcap"))]
+            CaptureSource::PcapNg(reader) => reader.link_type(),
+            #[cfg(feature = "libpcap")]
+            CaptureSource::Libpcap(reader) => reader.link_type(),
+        }
+    }
+
+    /// Name of the file format ("pcap" or "pcapng")
+    pub fn format(&self) -> &'static str {
+        match self {
+            #[cfg(not(feature = "libpcap"))]
+            CaptureSource::Pcap(_) => "pcap",
+            #[cfg(not(feature = "libpcap"))]
+            CaptureSource::PcapNg(_) => "pcapng",
+            #[cfg(feature = "libpcap")]
+            CaptureSource::Libpcap(_) => "pcap",
+        }
+    }
+
+    /// Section header information (pcapng only)
+    pub fn section(&self) -> Option<&CaptureSection> {
+        match self {
+            #[cfg(not(feature = "libpcap"))]
+            CaptureSource::PcapNg(reader) => Some(reader.section()),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+
+    /// Interfaces declared so far (pcapng only)
+    pub fn interfaces(&self) -> &[CaptureInterface] {
+        match self {
+            #[cfg(not(feature = "libpcap"))]
+            CaptureSource::PcapNg(reader) => reader.interfaces(),
+            #[allow(unreachable_patterns)]
+            _ => &[],
+        }
+    }
+
+    /// Address to host name mappings seen so far (pcapng only)
+    pub fn name_resolution(&self) -> Option<&HashMap<Vec<u8>, Vec<String>>> {
+        match self {
+            #[cfg(not(feature = "libpcap"))]
+            CaptureSource::PcapNg(reader) => Some(reader.name_resolution()),
+            #[allow(unreachable_patterns)]
+            _ => None,
+        }
+    }
+}
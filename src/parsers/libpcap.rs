@@ -0,0 +1,61 @@
+//! Capture file backend built on libpcap
+//!
+//! Only compiled with the `libpcap` cargo feature. libpcap has to be
+//! installed on the system both at build time and at runtime.
+
+use std::io;
+use std::sync::Mutex;
+use pcap::{Capture, Offline};
+use crate::parsers::capture::CaptureRecord;
+
+/// Reads capture records through libpcap
+pub(crate) struct LibpcapReader {
+    // libpcap handles are Send but not Sync, so the Mutex makes the reader
+    // shareable with Python threads
+    cap: Mutex<Capture<Offline>>,
+    link_type: u32,
+}
+
+impl LibpcapReader {
+    /// Open a capture file with libpcap
+    pub fn open(path: &str) -> io::Result<Self> {
+        let cap = Capture::from_file(path)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        let link_type = cap.get_datalink().0 as u32;
+        Ok(Self { cap: Mutex::new(cap), link_type })
+    }
+
+    /// Link-layer header type (LINKTYPE_*) of the file
+    pub fn link_type(&self) -> u32 {
+        self.link_type
+    }
+
+    /// Read the next record, or `None` at the end of the file
+    pub fn next_record(&mut self) -> io::Result<Option<CaptureRecord>> {
+        let cap = self.cap.get_mut()
+            .map_err(|_| io::Error::other("libpcap handle is poisoned"))?;
+
+        match cap.next_packet() {
+            Ok(packet) => {
+                // time_t is only 32 bits wide on some platforms
+                #[allow(clippy::useless_conversion)]
+                let ts_sec = i64::from(packet.header.ts.tv_sec);
+
+                // libpcap reports microsecond timestamps
+                Ok(Some(CaptureRecord {
+                    ts_sec,
+                    ts_nsec: packet.header.ts.tv_usec as u32 * 1000,
+                    original_length: packet.header.len,
+                    link_type: self.link_type,
+                    interface_id: None,
+                    interface_name: None,
+                    comments: Vec::new(),
+                    flags: None,
+                    data: packet.data.to_vec(),
+                }))
+            },
+            Err(pcap::Error::NoMorePackets) => Ok(None),
+            Err(e) => Err(io::Error::new(io::ErrorKind::InvalidData, e.to_string())),
+        }
+    }
+}
@@ -4,9 +4,17 @@
 //! raw bytes or PCAP files.
 
 // Declare submodules
+mod capture;
+#[cfg(feature = "libpcap")]
+mod libpcap;
 mod packet;
 mod pcap;
+#[cfg(not(feature = "libpcap"))]
+mod pcap_file;
+#[cfg(not(feature = "libpcap"))]
+mod pcapng;
 
 // Re-export parsing functions
 pub use packet::parse_packet;
-pub use pcap::parse_pcap_file;
\ No newline at end of file
+pub(crate) use packet::decode_frame;
+pub use pcap::{parse_pcap_file, PcapReader};
\ No newline at end of file
@@ -1,114 +1,456 @@
 use pyo3::prelude::*;
-use etherparse::{PacketHeaders, IpHeader, TransportHeader, SlicedPacket};
-use crate::models::ParsedPacket;
-use crate::headers::{Ethernet2Header, Ipv4Header, Ipv6Header, TcpHeader, UdpHeader};
+use etherparse::{ether_type, ip_number, ReadError};
+use crate::models::{PacketError, ParsedPacket};
+use crate::headers::{
+    decode_extensions, transport_checksum, ArpHeader, Ethernet2Header, Icmpv4Header, Icmpv6Header, Ipv4Header, Ipv6Header, LinuxSllHeader, TcpHeader,
+    UdpHeader, VlanHeader,
+};
+
+// Link-layer header types (LINKTYPE_* values, see tcpdump.org/linktypes.html)
+const LINKTYPE_NULL: u32 = 0;
+const LINKTYPE_ETHERNET: u32 = 1;
+const LINKTYPE_PPP: u32 = 9;
+const LINKTYPE_PPP_HDLC: u32 = 50;
+const LINKTYPE_RAW: u32 = 101;
+const LINKTYPE_LOOP: u32 = 108;
+const LINKTYPE_LINUX_SLL: u32 = 113;
+const LINKTYPE_IPV4: u32 = 228;
+const LINKTYPE_IPV6: u32 = 229;
+const LINKTYPE_LINUX_SLL2: u32 = 276;
+
+// DLT_RAW values some platforms write instead of LINKTYPE_RAW
+const DLT_RAW_BSD: u32 = 12;
+const DLT_RAW_OPENBSD: u32 = 14;
+
+// Address family values of the NULL/LOOP header that mean IPv6 (Linux,
+// NetBSD/OpenBSD, FreeBSD, macOS)
+const AF_INET: u32 = 2;
+const AF_INET6: [u32; 4] = [10, 24, 28, 30];
+
+// PPP protocol numbers
+const PPP_IPV4: u16 = 0x0021;
+const PPP_IPV6: u16 = 0x0057;
 
 /// Parse a raw packet from bytes
-/// 
+///
+/// Without a link type the frame is decoded as Ethernet, falling back to
+/// a bare IP packet if that fails. Pass the capture's link type to decode
+/// the frame exactly as captured.
+///
 /// Args:
 ///     data: Raw packet bytes (e.g., from a pcap file or network interface)
-/// 
+///     link_type: Link-layer header type (LINKTYPE_*) of the frame: 1
+///         (Ethernet), 101 (raw IP), 228 (IPv4), 229 (IPv6), 113 (Linux
+///         SLL), 276 (Linux SLL2), 0 (NULL), 108 (LOOP), 9 or 50 (PPP)
+///         (default: None, guess)
+///
 /// Returns:
 ///     ParsedPacket: A parsed packet with all detected headers and payload
-/// 
+///
 /// Raises:
-///     ValueError: If the packet cannot be parsed
+///     ValueError: If the packet cannot be parsed or the link type is not
+///         supported
 #[pyfunction]
-pub fn parse_packet(data: &[u8]) -> PyResult<ParsedPacket> {
-    // Try to parse the packet using etherparse
-    match PacketHeaders::from_ethernet_slice(data) {
-        Ok(headers) => {
-            // Create a new parsed packet
-            let mut parsed = ParsedPacket::new();
-            
-            // Parse link layer (Ethernet)
-            if let Some(link) = headers.link {
-                parsed.link = Some(Ethernet2Header::from(link));
-            }
-            
-            // Parse IP layer
-            if let Some(ip) = headers.ip {
-                match ip {
-                    IpHeader::Version4(ipv4, _) => {
-                        parsed.ipv4 = Some(Ipv4Header::from(ipv4));
-                    },
-                    IpHeader::Version6(ipv6, _) => {
-                        parsed.ipv6 = Some(Ipv6Header::from(ipv6));
-                    }
-                }
+#[pyo3(signature = (data, link_type = None))]
+pub fn parse_packet(data: &[u8], link_type: Option<u32>) -> PyResult<ParsedPacket> {
+    let mut parsed = decode_frame(data, link_type);
+    parsed.link_type = link_type;
+    match &parsed.error {
+        None => Ok(parsed),
+        Some(error) => Err(pyo3::exceptions::PyValueError::new_err(
+            format!("Failed to parse packet: {}", error)
+        )),
+    }
+}
+
+/// Decode a frame into a ParsedPacket, recording where decoding failed
+///
+/// A failed packet keeps the headers decoded before the failing layer, its
+/// payload holds the bytes from the failing header on, and its error field
+/// says which layer failed at which offset.
+///
+/// Without a link type the frame is decoded as Ethernet first and as a
+/// bare IP packet if that fails. When both fail, the attempt that got
+/// further into the frame is returned.
+pub(crate) fn decode_frame<'a>(data: &'a [u8], link_type: Option<u32>) -> ParsedPacket {
+    let first_layer: fn(&mut Decoder<'a>) -> Result<(), PacketError> = match link_type {
+        None => return guess_frame(data),
+        Some(LINKTYPE_ETHERNET) => Decoder::ethernet,
+        Some(LINKTYPE_RAW | DLT_RAW_BSD | DLT_RAW_OPENBSD) => Decoder::ip,
+        Some(LINKTYPE_IPV4) => Decoder::ipv4,
+        Some(LINKTYPE_IPV6) => Decoder::ipv6,
+        Some(LINKTYPE_LINUX_SLL) => Decoder::linux_sll,
+        Some(LINKTYPE_LINUX_SLL2) => Decoder::linux_sll2,
+        Some(LINKTYPE_NULL) => Decoder::null,
+        Some(LINKTYPE_LOOP) => Decoder::loopback,
+        Some(LINKTYPE_PPP | LINKTYPE_PPP_HDLC) => Decoder::ppp,
+        Some(other) => {
+            let mut packet = ParsedPacket::from_frame(data);
+            packet.payload = data.to_vec();
+            packet.error = Some(PacketError::new("link", 0, format!("Unsupported link type {}", other)));
+            return packet;
+        },
+    };
+    Decoder::new(data).run(first_layer)
+}
+
+/// Decode the original datagram quoted by an ICMP error message
+///
+/// Quotes within the quote aren't decoded, as errors are never sent about
+/// ICMP errors and a crafted packet could otherwise nest them deeply.
+fn decode_quote(data: &[u8]) -> ParsedPacket {
+    let mut decoder = Decoder::new(data);
+    decoder.quote = true;
+    decoder.run(Decoder::ip)
+}
+
+/// Decode a frame of unknown link type as Ethernet or else as bare IP
+fn guess_frame(data: &[u8]) -> ParsedPacket {
+    let ethernet = Decoder::new(data).run(Decoder::ethernet);
+    let ethernet_offset = match &ethernet.error {
+        None => return ethernet,
+        Some(error) => error.offset,
+    };
+
+    // If parsing as Ethernet fails, try parsing as IP directly
+    let ip = Decoder::new(data).run(Decoder::ip);
+    match &ip.error {
+        Some(error) if error.offset <= ethernet_offset => ethernet,
+        _ => ip,
+    }
+}
+
+/// Walks the headers of one frame, filling in a ParsedPacket
+struct Decoder<'a> {
+    data: &'a [u8],
+    // Start of the first byte not decoded yet
+    offset: usize,
+    // End of the IP payload according to the IP header, if known
+    ip_end: Option<usize>,
+    // Whether the data is the quote of an ICMP error message
+    quote: bool,
+    packet: ParsedPacket,
+}
+
+impl<'a> Decoder<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            offset: 0,
+            ip_end: None,
+            quote: false,
+            packet: ParsedPacket::from_frame(data),
+        }
+    }
+
+    /// Decode the frame starting with the given layer and finish the packet
+    fn run(mut self, first_layer: fn(&mut Self) -> Result<(), PacketError>) -> ParsedPacket {
+        if let Err(error) = first_layer(&mut self) {
+            self.packet.error = Some(error);
+        }
+        self.packet.layer_spans.push(("payload", self.offset, self.rest().len()));
+        self.packet.payload = self.rest().to_vec();
+        // Bytes past the end of the IP packet, such as Ethernet padding
+        if let Some(ip_end) = self.ip_end {
+            self.packet.trailer_len = self.data.len().saturating_sub(ip_end.max(self.offset));
+        }
+        self.packet
+    }
+
+    /// Bytes that haven't been decoded yet
+    fn rest(&self) -> &'a [u8] {
+        &self.data[self.offset..]
+    }
+
+    /// Bytes that haven't been decoded yet and belong to the IP packet,
+    /// leaving out a trailer such as Ethernet padding
+    fn ip_rest(&self) -> &'a [u8] {
+        let end = self.ip_end.map_or(self.data.len(), |end| end.clamp(self.offset, self.data.len()));
+        &self.data[self.offset..end]
+    }
+
+    /// Move past a header, given the bytes left after it
+    fn advance(&mut self, rest: &'a [u8]) {
+        self.offset = self.data.len() - rest.len();
+    }
+
+    /// Move past a header and record its span, given the bytes left after it
+    fn span_to(&mut self, layer: &'static str, rest: &'a [u8]) {
+        let start = self.offset;
+        self.advance(rest);
+        self.span(layer, start);
+    }
+
+    /// Record that a layer spans from start up to the current offset
+    fn span(&mut self, layer: &'static str, start: usize) {
+        self.packet.layer_spans.push((layer, start, self.offset - start));
+    }
+
+    /// Describe a failure to decode the header at the current offset
+    fn error(&self, layer: &str, e: ReadError) -> PacketError {
+        let message = e.to_string();
+        let message = message.trim_start_matches("ReadError: ");
+        PacketError::new(layer, self.offset, message)
+    }
+
+    /// Take a fixed-size link-layer header off the front of the rest
+    fn take(&mut self, layer: &str, len: usize) -> Result<&'a [u8], PacketError> {
+        let rest = self.rest();
+        if rest.len() < len {
+            return Err(self.error(layer, ReadError::UnexpectedEndOfSlice(len)));
+        }
+        self.offset += len;
+        Ok(&rest[..len])
+    }
+
+    fn ethernet(&mut self) -> Result<(), PacketError> {
+        let (ethernet, rest) = etherparse::Ethernet2Header::from_slice(self.rest())
+            .map_err(|e| self.error("ethernet", e))?;
+        let mut next = ethernet.ether_type;
+        self.packet.link = Some(Ethernet2Header::from(ethernet));
+        self.span_to("link", rest);
+
+        // Keep the outer and inner tag; further stacked tags are skipped
+        while matches!(
+            next,
+            ether_type::VLAN_TAGGED_FRAME | ether_type::PROVIDER_BRIDGING | ether_type::VLAN_DOUBLE_TAGGED_FRAME
+        ) {
+            let (vlan, rest) = etherparse::SingleVlanHeader::from_slice(self.rest())
+                .map_err(|e| self.error("vlan", e))?;
+            next = vlan.ether_type;
+            if self.packet.vlan.is_none() {
+                self.packet.vlan = Some(VlanHeader::from(vlan));
+                self.span_to("vlan", rest);
+            } else if self.packet.vlan_inner.is_none() {
+                self.packet.vlan_inner = Some(VlanHeader::from(vlan));
+                self.span_to("vlan_inner", rest);
+            } else {
+                self.advance(rest);
             }
-            
-            // Parse transport layer
-            if let Some(transport) = headers.transport {
-                match transport {
-                    TransportHeader::Tcp(tcp) => {
-                        parsed.tcp = Some(TcpHeader::from(tcp));
-                    },
-                    TransportHeader::Udp(udp) => {
-                        parsed.udp = Some(UdpHeader::from(udp));
-                    },
-                    // Other transport protocols can be handled here
-                    _ => {
-                        // We're ignoring other transport protocols for now
-                    }
+        }
+        self.ether_payload(next)
+    }
+
+    /// Decode what follows a link-layer header, given its EtherType
+    fn ether_payload(&mut self, next: u16) -> Result<(), PacketError> {
+        match next {
+            ether_type::IPV4 => self.ipv4(),
+            ether_type::IPV6 => self.ipv6(),
+            ether_type::ARP => self.arp(),
+            _ => Ok(()),
+        }
+    }
+
+    fn arp(&mut self) -> Result<(), PacketError> {
+        let (arp, rest) = ArpHeader::from_slice(self.rest())
+            .map_err(|e| self.error("arp", e))?;
+        self.packet.arp = Some(arp);
+        self.span_to("arp", rest);
+        Ok(())
+    }
+
+    /// Linux cooked capture header
+    fn linux_sll(&mut self) -> Result<(), PacketError> {
+        let (sll, rest) = LinuxSllHeader::from_slice_v1(self.rest())
+            .ok_or_else(|| self.error("linux_sll", ReadError::UnexpectedEndOfSlice(16)))?;
+        self.linux_sll_payload(sll, rest)
+    }
+
+    /// Linux cooked capture v2 header
+    fn linux_sll2(&mut self) -> Result<(), PacketError> {
+        let (sll, rest) = LinuxSllHeader::from_slice_v2(self.rest())
+            .ok_or_else(|| self.error("linux_sll", ReadError::UnexpectedEndOfSlice(20)))?;
+        self.linux_sll_payload(sll, rest)
+    }
+
+    fn linux_sll_payload(&mut self, sll: LinuxSllHeader, rest: &'a [u8]) -> Result<(), PacketError> {
+        let protocol = sll.protocol();
+        self.packet.linux_sll = Some(sll);
+        self.span_to("linux_sll", rest);
+        self.ether_payload(protocol)
+    }
+
+    /// BSD loopback header: address family in the capturing host's byte order
+    fn null(&mut self) -> Result<(), PacketError> {
+        let header = self.take("null", 4)?;
+        self.packet.link_header = header.to_vec();
+        self.span("null", self.offset - 4);
+        let family = u32::from_le_bytes([header[0], header[1], header[2], header[3]]);
+        // Address families are small, so a big-endian value has its
+        // significant byte at the end
+        let family = if family > 0xffff { family.swap_bytes() } else { family };
+        self.address_family(family)
+    }
+
+    /// OpenBSD loopback header: address family in network byte order
+    fn loopback(&mut self) -> Result<(), PacketError> {
+        let header = self.take("loop", 4)?;
+        self.packet.link_header = header.to_vec();
+        self.span("loop", self.offset - 4);
+        self.address_family(u32::from_be_bytes([header[0], header[1], header[2], header[3]]))
+    }
+
+    fn address_family(&mut self, family: u32) -> Result<(), PacketError> {
+        if family == AF_INET {
+            self.ipv4()
+        } else if AF_INET6.contains(&family) {
+            self.ipv6()
+        } else {
+            Ok(())
+        }
+    }
+
+    /// PPP header: optional HDLC address/control bytes, then a protocol
+    /// field that may be compressed to a single byte
+    fn ppp(&mut self) -> Result<(), PacketError> {
+        let start = self.offset;
+        if self.rest().starts_with(&[0xff, 0x03]) {
+            self.take("ppp", 2)?;
+        }
+        let protocol = match self.rest().first() {
+            Some(first) if first & 1 == 1 => u16::from(self.take("ppp", 1)?[0]),
+            _ => {
+                let field = self.take("ppp", 2)?;
+                u16::from_be_bytes([field[0], field[1]])
+            },
+        };
+        self.packet.link_header = self.data[start..self.offset].to_vec();
+        self.span("ppp", start);
+        match protocol {
+            PPP_IPV4 => self.ipv4(),
+            PPP_IPV6 => self.ipv6(),
+            _ => Ok(()),
+        }
+    }
+
+    /// Decode an IP packet of either version, based on its version field
+    fn ip(&mut self) -> Result<(), PacketError> {
+        match self.rest().first().map(|b| b >> 4) {
+            Some(4) => self.ipv4(),
+            Some(6) => self.ipv6(),
+            Some(version) => Err(self.error("ip", ReadError::IpUnsupportedVersion(version))),
+            None => Err(self.error("ip", ReadError::UnexpectedEndOfSlice(1))),
+        }
+    }
+
+    fn ipv4(&mut self) -> Result<(), PacketError> {
+        let start = self.offset;
+        let (ipv4, rest) = etherparse::Ipv4Header::from_slice(self.rest())
+            .map_err(|e| self.error("ipv4", e))?;
+        self.ip_end = Some(self.offset + usize::from(ipv4.total_len()));
+        let fragmented = ipv4.is_fragmenting_payload();
+        let protocol = ipv4.protocol;
+        self.packet.ipv4 = Some(Ipv4Header::from(ipv4));
+        self.advance(rest);
+
+        let (_, protocol, rest) = etherparse::Ipv4Extensions::from_slice(protocol, self.rest())
+            .map_err(|e| self.error("ipv4", e))?;
+        self.advance(rest);
+        self.span("ipv4", start);
+
+        // Only fragments starting at offset 0 carry the transport header,
+        // and even those only carry part of the payload
+        if fragmented {
+            return Ok(());
+        }
+        self.transport(protocol)
+    }
+
+    fn ipv6(&mut self) -> Result<(), PacketError> {
+        let start = self.offset;
+        let (ipv6, rest) = etherparse::Ipv6Header::from_slice(self.rest())
+            .map_err(|e| self.error("ipv6", e))?;
+        // A payload length of zero is used by jumbograms (RFC 2675)
+        self.ip_end = match ipv6.payload_length {
+            0 => None,
+            length => Some(self.offset + 40 + usize::from(length)),
+        };
+        let next_header = ipv6.next_header;
+        self.packet.ipv6 = Some(Ipv6Header::from(ipv6.clone()));
+        self.advance(rest);
+
+        let (extensions, next_header, rest) = decode_extensions(next_header, self.rest())
+            .map_err(|e| self.error("ipv6", e))?;
+        let ipv6 = Ipv6Header::with_extensions(ipv6, extensions);
+        let fragmented = ipv6.is_fragmenting_payload();
+        self.packet.ipv6 = Some(ipv6);
+        self.advance(rest);
+        self.span("ipv6", start);
+
+        if fragmented {
+            return Ok(());
+        }
+        self.transport(next_header)
+    }
+
+    /// Check the checksum of the TCP or UDP segment at the current offset
+    ///
+    /// Returns None if the capture cut off part of the segment.
+    fn transport_checksum_valid(&self, protocol: u8) -> Option<bool> {
+        let segment = self.data.get(self.offset..self.ip_end?)?;
+        let (source, destination) = match (&self.packet.ipv4, &self.packet.ipv6) {
+            (Some(ipv4), _) => (ipv4.source().to_vec(), ipv4.destination().to_vec()),
+            (None, Some(ipv6)) => (ipv6.source().to_vec(), ipv6.final_destination().to_vec()),
+            (None, None) => return None,
+        };
+        Some(transport_checksum(&source, &destination, protocol, &[segment]) == 0)
+    }
+
+    fn transport(&mut self, protocol: u8) -> Result<(), PacketError> {
+        match protocol {
+            ip_number::TCP => {
+                let (tcp, rest) = etherparse::TcpHeader::from_slice(self.rest())
+                    .map_err(|e| self.error("tcp", e))?;
+                let mut tcp = TcpHeader::from(tcp);
+                tcp.checksum_valid = self.transport_checksum_valid(protocol);
+                self.packet.tcp = Some(tcp);
+                self.span_to("tcp", rest);
+            },
+            ip_number::UDP => {
+                let (udp, rest) = etherparse::UdpHeader::from_slice(self.rest())
+                    .map_err(|e| self.error("udp", e))?;
+                let mut udp = UdpHeader::from(udp);
+                udp.checksum_valid = if udp.checksum() == 0 {
+                    // Zero means "no checksum", which only IPv4 allows
+                    Some(self.packet.ipv4.is_some())
+                } else {
+                    self.transport_checksum_valid(protocol)
+                };
+                self.packet.udp = Some(udp);
+                self.span_to("udp", rest);
+            },
+            ip_number::ICMP => {
+                let message = self.ip_rest();
+                let (icmpv4, body) = Icmpv4Header::from_slice(message)
+                    .map_err(|e| self.error("icmpv4", e))?;
+                if icmpv4.is_error() {
+                    self.decode_quote(body);
                 }
-            }
-            
-            // Extract payload if available
-            if !headers.payload.is_empty() {
-                parsed.set_payload(headers.payload.to_vec());
-            }
-            
-            Ok(parsed)
-        },
-        // If parsing as Ethernet fails, try parsing as IP directly
-        Err(_) => {
-            match SlicedPacket::from_ip(data) {
-                Ok(packet) => {
-                    let mut parsed = ParsedPacket::new();
-                    
-                    // Parse IP layer
-                    if let Some(ip) = packet.ip {
-                        match ip {
-                            etherparse::InternetSlice::Ipv4(ipv4, _) => {
-                                parsed.ipv4 = Some(Ipv4Header::from(ipv4.to_header()));
-                            },
-                            etherparse::InternetSlice::Ipv6(ipv6, _) => {
-                                parsed.ipv6 = Some(Ipv6Header::from(ipv6.to_header()));
-                            }
-                        }
-                    }
-                    
-                    // Parse transport layer
-                    if let Some(transport) = packet.transport {
-                        match transport {
-                            etherparse::TransportSlice::Tcp(tcp) => {
-                                parsed.tcp = Some(TcpHeader::from(tcp.to_header()));
-                            },
-                            etherparse::TransportSlice::Udp(udp) => {
-                                parsed.udp = Some(UdpHeader::from(udp.to_header()));
-                            },
-                            // Other transport protocols can be handled here
-                            _ => {
-                                // We're ignoring other transport protocols for now
-                            }
-                        }
-                    }
-                    
-                    // Extract payload if available
-                    if !packet.payload.is_empty() {
-                        parsed.set_payload(packet.payload.to_vec());
-                    }
-                    
-                    Ok(parsed)
-                },
-                Err(e) => {
-                    // If all parsing attempts fail, return an error
-                    Err(pyo3::exceptions::PyValueError::new_err(
-                        format!("Failed to parse packet: {}", e)
-                    ))
+                self.packet.icmpv4 = Some(icmpv4);
+                // The trailer stays in the packet payload
+                let rest = &self.rest()[message.len() - body.len()..];
+                self.span_to("icmpv4", rest);
+            },
+            ip_number::IPV6_ICMP => {
+                let message = self.ip_rest();
+                let (icmpv6, body) = Icmpv6Header::from_slice(message)
+                    .map_err(|e| self.error("icmpv6", e))?;
+                if icmpv6.is_error() {
+                    self.decode_quote(body);
                 }
-            }
+                self.packet.icmpv6 = Some(icmpv6);
+                let rest = &self.rest()[message.len() - body.len()..];
+                self.span_to("icmpv6", rest);
+            },
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Decode the original datagram an ICMP error message quotes
+    fn decode_quote(&mut self, datagram: &[u8]) {
+        if !self.quote && !datagram.is_empty() {
+            self.packet.icmp_original = Some(Box::new(decode_quote(datagram)));
         }
     }
-}
\ No newline at end of file
+}
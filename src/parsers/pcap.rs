@@ -1,63 +1,349 @@
+use std::io;
 use pyo3::prelude::*;
-use pyo3::types::PyList;
-use crate::parsers::packet::parse_packet;
+use pyo3::types::{PyBytes, PyDict, PyList};
+use crate::models::{CaptureInterface, CaptureSection, CaptureStats, PacketError, ParsedPacket};
+use crate::parsers::capture::{CaptureRecord, CaptureSource};
+use crate::parsers::packet::decode_frame;
 
-// pcap is only imported on Unix systems since libpcap isn't well-supported on Windows
-#[cfg(unix)]
-use pcap::Capture;
+/// What a reader does with frames that can't be decoded
+#[derive(Clone, Copy, PartialEq)]
+enum ErrorMode {
+    // Leave the frame out
+    Skip,
+    // Raise a ValueError
+    Raise,
+    // Yield a ParsedPacket with the error field set
+    Include,
+}
 
-/// Parse a PCAP file and extract all packets
-/// 
+impl ErrorMode {
+    fn parse(errors: &str) -> PyResult<Self> {
+        match errors {
+            "skip" => Ok(Self::Skip),
+            "raise" => Ok(Self::Raise),
+            "include" => Ok(Self::Include),
+            _ => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Invalid errors mode: {:?} (expected \"skip\", \"raise\" or \"include\")",
+                errors
+            ))),
+        }
+    }
+}
+
+/// Streaming reader over the packets of a PCAP or pcapng file
+///
+/// Packets are read and parsed one at a time, so memory use stays flat
+/// no matter how large the capture is. The file format is detected from
+/// its magic number. The reader implements the iterator and context
+/// manager protocols:
+///
+///     with PcapReader("capture.pcap") as reader:
+///         for packet in reader:
+///             ...
+///
+/// The errors argument decides what happens to frames that can't be
+/// decoded: "skip" leaves them out, "raise" raises a ValueError and
+/// "include" yields them as ParsedPackets whose error attribute says which
+/// layer failed at which offset (the payload then holds the undecoded
+/// bytes). A file that is cut off or corrupt mid-way ends the iteration;
+/// with "include" an extra packet with a "capture" layer error is yielded
+/// first, with "raise" a ValueError is raised. The stats attribute counts
+/// the frames read, parsed and failed so far.
+///
 /// Args:
 ///     path: Path to the PCAP file
-/// 
-/// Returns:
-///     List[ParsedPacket]: A list of parsed packets
-/// 
+///     errors: "skip", "raise" or "include" (default: "skip")
+///
 /// Raises:
 ///     IOError: If the file cannot be opened
-///     ValueError: If the file is not a valid PCAP file
-#[cfg(unix)]
-#[pyfunction]
-pub fn parse_pcap_file(py: Python<'_>, path: String) -> PyResult<Py<PyList>> {
-    // Open the PCAP file
-    let mut cap = match Capture::from_file(path) {
-        Ok(cap) => cap,
-        Err(e) => {
-            return Err(pyo3::exceptions::PyIOError::new_err(
-                format!("Failed to open pcap file: {}", e)
+///     ValueError: If the file is not a valid PCAP file or errors is unknown
+#[pyclass]
+pub struct PcapReader {
+    // The capture source is dropped on close()
+    source: Option<CaptureSource>,
+    
+    // Index of the next frame in the file, counting skipped frames too so
+    // numbering matches other tools
+    frame_index: u64,
+    
+    errors: ErrorMode,
+    stats: CaptureStats,
+    
+    // Set once the end of the file or a read error has been reached
+    exhausted: bool,
+}
+
+#[pymethods]
+impl PcapReader {
+    /// Open a PCAP file for streaming
+    #[new]
+    #[pyo3(signature = (path, errors = "skip"))]
+    pub fn new(path: String, errors: &str) -> PyResult<Self> {
+        let errors = ErrorMode::parse(errors)?;
+        let source = CaptureSource::open(&path).map_err(capture_error)?;
+        Ok(Self {
+            source: Some(source),
+            frame_index: 0,
+            errors,
+            stats: CaptureStats::default(),
+            exhausted: false,
+        })
+    }
+
+    /// Return the reader itself as the iterator
+    pub fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Read and parse the next packet of the file
+    ///
+    /// Raises:
+    ///     ValueError: If the reader has been closed, or with errors="raise"
+    ///         if a frame can't be decoded or the file can't be read further
+    ///     IOError: With errors="raise", if reading the file fails
+    pub fn __next__(&mut self) -> PyResult<Option<ParsedPacket>> {
+        if self.is_closed() {
+            return Err(pyo3::exceptions::PyValueError::new_err(
+                "I/O operation on closed PcapReader"
             ));
         }
-    };
+        self.next_parsed()
+    }
+
+    /// Enter the runtime context (returns the reader itself)
+    pub fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Exit the runtime context, closing the reader
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    pub fn __exit__(
+        &mut self,
+        _exc_type: Option<PyObject>,
+        _exc_value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> bool {
+        self.close();
+        // Never swallow exceptions raised inside the with block
+        false
+    }
+
+    /// Close the underlying file. Calling close() more than once is allowed.
+    pub fn close(&mut self) {
+        self.source = None;
+    }
+
+    /// Check if the reader has been closed
+    #[getter]
+    pub fn closed(&self) -> bool {
+        self.is_closed()
+    }
     
-    // Create a Python list to store the parsed packets
-    let packets = PyList::empty(py);
+    /// Get the counts of frames read, parsed and failed so far
+    ///
+    /// Still available after the reader has been closed.
+    #[getter]
+    pub fn stats(&self) -> CaptureStats {
+        self.stats.clone()
+    }
     
-    // Process each packet in the PCAP file
-    // pcap kütüphanesinde doğru iterator kullanımı:
-    while let Ok(packet) = cap.next_packet() {
-        // Parse the packet
-        match parse_packet(packet.data) {
-            Ok(parsed) => {
-                // Add the parsed packet to the list - deprecated warning'i düzelttik
-                packets.append(parsed.into_pyobject(py)?)?;
-            },
-            Err(_) => {
-                // Skip packets that can't be parsed
-                continue;
+    /// Get the link-layer header type (LINKTYPE_*) of the file
+    ///
+    /// For pcapng files this is the link type of the first interface, or 0
+    /// until its Interface Description Block has been read.
+    ///
+    /// Raises:
+    ///     ValueError: If the reader has been closed
+    #[getter]
+    pub fn link_type(&self) -> PyResult<u32> {
+        Ok(self.source()?.link_type())
+    }
+    
+    /// Get the file format, "pcap" or "pcapng"
+    ///
+    /// Raises:
+    ///     ValueError: If the reader has been closed
+    #[getter]
+    pub fn format(&self) -> PyResult<&'static str> {
+        Ok(self.source()?.format())
+    }
+    
+    /// Get the Section Header Block information (None for pcap files)
+    ///
+    /// Raises:
+    ///     ValueError: If the reader has been closed
+    #[getter]
+    pub fn section(&self) -> PyResult<Option<CaptureSection>> {
+        Ok(self.source()?.section().cloned())
+    }
+    
+    /// Get the interfaces declared so far in the current pcapng section
+    ///
+    /// Interface Description Blocks may appear anywhere in a file, so the
+    /// list can grow while packets are read. Empty for pcap files.
+    ///
+    /// Raises:
+    ///     ValueError: If the reader has been closed
+    #[getter]
+    pub fn interfaces(&self) -> PyResult<Vec<CaptureInterface>> {
+        Ok(self.source()?.interfaces().to_vec())
+    }
+    
+    /// Get the address to host name mappings read so far in the current
+    /// pcapng section
+    ///
+    /// Returns:
+    ///     Dict[bytes, List[str]]: Names keyed by 4-byte IPv4 or 16-byte
+    ///     IPv6 address, from pcapng Name Resolution Blocks
+    ///
+    /// Raises:
+    ///     ValueError: If the reader has been closed
+    #[getter]
+    pub fn name_resolution<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let result = PyDict::new(py);
+        if let Some(names) = self.source()?.name_resolution() {
+            for (address, hosts) in names {
+                result.set_item(PyBytes::new(py, address), hosts)?;
             }
         }
+        Ok(result)
     }
-    
-    // Return the list of parsed packets
-    Ok(packets.into())
 }
 
-/// Stub function for Windows systems
-#[cfg(not(unix))]
+impl PcapReader {
+    fn is_closed(&self) -> bool {
+        self.source.is_none()
+    }
+
+    /// Get the open capture source or fail if the reader was closed
+    fn source(&self) -> PyResult<&CaptureSource> {
+        self.source.as_ref().ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err("I/O operation on closed PcapReader")
+        })
+    }
+
+    /// Advance to the next packet, handling failures per the errors mode
+    ///
+    /// Returns None once the end of the file is reached. Reading also stops
+    /// at the first record that can't be read.
+    fn next_parsed(&mut self) -> PyResult<Option<ParsedPacket>> {
+        let source = match self.source.as_mut() {
+            Some(source) if !self.exhausted => source,
+            _ => return Ok(None),
+        };
+
+        loop {
+            let record = match source.next_record() {
+                Ok(Some(record)) => record,
+                Ok(None) => {
+                    self.exhausted = true;
+                    return Ok(None);
+                },
+                Err(e) => {
+                    self.exhausted = true;
+                    self.stats.read_error = Some(e.to_string());
+                    return match self.errors {
+                        ErrorMode::Skip => Ok(None),
+                        ErrorMode::Raise => Err(capture_error(e)),
+                        ErrorMode::Include => {
+                            let mut parsed = ParsedPacket::new();
+                            parsed.frame_index = self.frame_index;
+                            parsed.error = Some(PacketError::new("capture", 0, e.to_string()));
+                            Ok(Some(parsed))
+                        },
+                    };
+                },
+            };
+
+            let frame_index = self.frame_index;
+            self.frame_index += 1;
+            self.stats.frames_read += 1;
+
+            let mut parsed = decode_frame(&record.data, Some(record.link_type));
+            apply_record(&mut parsed, record, frame_index);
+            let error = match &parsed.error {
+                None => {
+                    self.stats.packets_parsed += 1;
+                    return Ok(Some(parsed));
+                },
+                Some(error) => error,
+            };
+
+            self.stats.packets_failed += 1;
+            match self.errors {
+                ErrorMode::Skip => continue,
+                ErrorMode::Raise => {
+                    return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                        "Failed to parse frame {}: {}", frame_index, error
+                    )));
+                },
+                ErrorMode::Include => return Ok(Some(parsed)),
+            }
+        }
+    }
+}
+
+/// Copy the metadata of a capture record onto the packet parsed from it
+fn apply_record(parsed: &mut ParsedPacket, record: CaptureRecord, frame_index: u64) {
+    parsed.set_capture_info(
+        record.ts_sec,
+        record.ts_nsec,
+        record.original_length,
+        frame_index,
+    );
+    parsed.link_type = Some(record.link_type);
+    parsed.interface_id = record.interface_id;
+    parsed.interface_name = record.interface_name;
+    parsed.comments = record.comments;
+    parsed.packet_flags = record.flags;
+}
+
+/// Convert an error from a capture source into a Python exception
+fn capture_error(e: io::Error) -> PyErr {
+    match e.kind() {
+        io::ErrorKind::InvalidData | io::ErrorKind::UnexpectedEof => {
+            pyo3::exceptions::PyValueError::new_err(format!("Invalid pcap file: {}", e))
+        },
+        _ => pyo3::exceptions::PyIOError::new_err(format!("Failed to open pcap file: {}", e)),
+    }
+}
+
+/// Parse a PCAP file and extract all packets
+///
+/// The whole capture is loaded into memory; use PcapReader to stream
+/// large files instead.
+///
+/// Args:
+///     path: Path to the PCAP file
+///     errors: What to do with frames that can't be decoded: "skip",
+///         "raise" or "include" (default: "skip"); see PcapReader
+///     with_stats: Also return the frame counts (default: False)
+///
+/// Returns:
+///     List[ParsedPacket]: A list of parsed packets, or a
+///     (List[ParsedPacket], CaptureStats) tuple if with_stats is True
+///
+/// Raises:
+///     IOError: If the file cannot be opened
+///     ValueError: If the file is not a valid PCAP file, or with
+///         errors="raise" if a frame can't be decoded
 #[pyfunction]
-pub fn parse_pcap_file(_py: Python<'_>, _path: String) -> PyResult<Py<PyList>> {
-    Err(pyo3::exceptions::PyNotImplementedError::new_err(
-        "parse_pcap_file is not supported on Windows."
-    ))
+#[pyo3(signature = (path, errors = "skip", with_stats = false))]
+pub fn parse_pcap_file(py: Python<'_>, path: String, errors: &str, with_stats: bool) -> PyResult<PyObject> {
+    // Open the PCAP file
+    let mut reader = PcapReader::new(path, errors)?;
+
+    // Create a Python list to store the parsed packets
+    let packets = PyList::empty(py);
+
+    // Process each packet in the PCAP file
+    while let Some(parsed) = reader.next_parsed()? {
+        packets.append(parsed.into_pyobject(py)?)?;
+    }
+
+    if with_stats {
+        Ok((packets, reader.stats).into_pyobject(py)?.into_any().unbind())
+    } else {
+        Ok(packets.into_any().unbind())
+    }
 }
@@ -0,0 +1,150 @@
+//! Pure-Rust reader for the classic libpcap file format
+//!
+//! Supports both byte orders and both the microsecond and nanosecond
+//! timestamp variants, without linking against libpcap.
+
+use std::io::{self, Read};
+use crate::parsers::capture::CaptureRecord;
+
+/// Magic number of microsecond-resolution files (as written by the capturer)
+const MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+
+/// Magic number of nanosecond-resolution files (as written by the capturer)
+const MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+
+/// Size of the global file header in bytes
+const FILE_HEADER_LEN: usize = 24;
+
+/// Size of each per-record header in bytes
+const RECORD_HEADER_LEN: usize = 16;
+
+/// Largest record we accept; anything bigger means the file is corrupt
+pub(crate) const MAX_RECORD_LEN: u32 = 64 * 1024 * 1024;
+
+/// Streaming reader over the records of a classic pcap file
+pub(crate) struct PcapFileReader<R: Read> {
+    reader: R,
+    // True if the file was written on a big-endian machine
+    big_endian: bool,
+    // True if the timestamp fraction is in nanoseconds instead of microseconds
+    nanosecond: bool,
+    link_type: u32,
+}
+
+impl<R: Read> PcapFileReader<R> {
+    /// Read the global header and prepare to read records
+    ///
+    /// Fails with `InvalidData` if the magic number isn't a pcap one.
+    pub fn new(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; FILE_HEADER_LEN];
+        reader.read_exact(&mut header).map_err(|e| match e.kind() {
+            io::ErrorKind::UnexpectedEof => invalid_data("file is too short for a pcap header"),
+            _ => e,
+        })?;
+
+        let magic = [header[0], header[1], header[2], header[3]];
+        let (big_endian, nanosecond) = if magic == MAGIC_MICROS.to_le_bytes() {
+            (false, false)
+        } else if magic == MAGIC_MICROS.to_be_bytes() {
+            (true, false)
+        } else if magic == MAGIC_NANOS.to_le_bytes() {
+            (false, true)
+        } else if magic == MAGIC_NANOS.to_be_bytes() {
+            (true, true)
+        } else {
+            return Err(invalid_data("unknown pcap magic number"));
+        };
+
+        let mut result = Self {
+            reader,
+            big_endian,
+            nanosecond,
+            link_type: 0,
+        };
+
+        // Bytes 4..20 hold the version, the (unused) timezone offset, the
+        // (unused) timestamp accuracy and the snaplen. The upper bits of the
+        // link type field carry FCS information
+        result.link_type = result.u32_at(&header, 20) & 0x0fff_ffff;
+
+        Ok(result)
+    }
+
+    /// Link-layer header type (LINKTYPE_*) of every record in the file
+    pub fn link_type(&self) -> u32 {
+        self.link_type
+    }
+
+    /// Read the next record
+    ///
+    /// Returns `Ok(None)` at a clean end of file and an `UnexpectedEof`
+    /// error if the file ends in the middle of a record.
+    pub fn next_record(&mut self) -> io::Result<Option<CaptureRecord>> {
+        let mut header = [0u8; RECORD_HEADER_LEN];
+        if !read_fully(&mut self.reader, &mut header)? {
+            return Ok(None);
+        }
+
+        let ts_sec = self.u32_at(&header, 0);
+        let ts_frac = self.u32_at(&header, 4);
+        let captured_length = self.u32_at(&header, 8);
+        let original_length = self.u32_at(&header, 12);
+
+        if captured_length > MAX_RECORD_LEN {
+            return Err(invalid_data("pcap record length is implausibly large"));
+        }
+
+        let mut data = vec![0u8; captured_length as usize];
+        self.reader.read_exact(&mut data)?;
+
+        Ok(Some(CaptureRecord {
+            ts_sec: i64::from(ts_sec),
+            ts_nsec: if self.nanosecond { ts_frac } else { ts_frac.saturating_mul(1000) },
+            original_length,
+            link_type: self.link_type,
+            interface_id: None,
+            interface_name: None,
+            comments: Vec::new(),
+            flags: None,
+            data,
+        }))
+    }
+
+    /// Decode a u32 field in the byte order of the file
+    fn u32_at(&self, buf: &[u8], offset: usize) -> u32 {
+        let bytes = [buf[offset], buf[offset + 1], buf[offset + 2], buf[offset + 3]];
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+}
+
+/// Fill `buf` completely from `reader`
+///
+/// Returns `Ok(false)` if the reader was already at the end of its data and
+/// an `UnexpectedEof` error if it ran out part way through `buf`.
+pub(crate) fn read_fully<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::UnexpectedEof,
+                    "capture file ends in the middle of a record",
+                ))
+            }
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(true)
+}
+
+/// Build an `InvalidData` error with the given message
+pub(crate) fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
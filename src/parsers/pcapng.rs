@@ -0,0 +1,496 @@
+//! Pure-Rust reader for the pcapng file format
+//!
+//! Understands Section Header, Interface Description, Enhanced Packet,
+//! Simple Packet, (obsolete) Packet and Name Resolution blocks. All other
+//! block types are skipped.
+
+use std::collections::HashMap;
+use std::io::{self, Read};
+use crate::models::{CaptureInterface, CaptureSection};
+use crate::parsers::capture::CaptureRecord;
+use crate::parsers::pcap_file::{invalid_data, read_fully, MAX_RECORD_LEN};
+
+/// Block type of the Section Header Block (also the file magic)
+pub(crate) const SECTION_HEADER_BLOCK: u32 = 0x0a0d_0d0a;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const PACKET_BLOCK: u32 = 0x0000_0002;
+const SIMPLE_PACKET_BLOCK: u32 = 0x0000_0003;
+const NAME_RESOLUTION_BLOCK: u32 = 0x0000_0004;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+
+/// Byte-order magic stored in every Section Header Block
+const BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+
+// Option codes shared by all blocks
+const OPT_END_OF_OPT: u16 = 0;
+const OPT_COMMENT: u16 = 1;
+
+// Section Header Block options
+const SHB_HARDWARE: u16 = 2;
+const SHB_OS: u16 = 3;
+const SHB_USERAPPL: u16 = 4;
+
+// Interface Description Block options
+const IF_NAME: u16 = 2;
+const IF_DESCRIPTION: u16 = 3;
+const IF_TSRESOL: u16 = 9;
+const IF_FILTER: u16 = 11;
+const IF_OS: u16 = 12;
+const IF_TSOFFSET: u16 = 14;
+
+// Enhanced Packet Block options
+const EPB_FLAGS: u16 = 2;
+
+// Name Resolution Block record types
+const NRB_RECORD_END: u16 = 0;
+const NRB_RECORD_IPV4: u16 = 1;
+const NRB_RECORD_IPV6: u16 = 2;
+
+/// Timestamp units per second when an interface has no if_tsresol option
+const DEFAULT_TS_RESOLUTION: u64 = 1_000_000;
+
+/// Streaming reader over the packet records of a pcapng file
+pub(crate) struct PcapNgReader<R: Read> {
+    reader: R,
+    // Byte order of the current section
+    big_endian: bool,
+    section: CaptureSection,
+    // Interfaces of the current section, indexed by interface ID
+    interfaces: Vec<CaptureInterface>,
+    // Address to host name mappings from the Name Resolution Blocks of the
+    // current section
+    name_resolution: HashMap<Vec<u8>, Vec<String>>,
+}
+
+/// One decoded option of a block
+struct BlockOption<'a> {
+    code: u16,
+    value: &'a [u8],
+}
+
+impl<R: Read> PcapNgReader<R> {
+    /// Read the leading Section Header Block and prepare to read records
+    ///
+    /// Fails with `InvalidData` if the file doesn't start with a pcapng
+    /// Section Header Block.
+    pub fn new(reader: R) -> io::Result<Self> {
+        let mut result = Self {
+            reader,
+            big_endian: false,
+            section: CaptureSection::default(),
+            interfaces: Vec::new(),
+            name_resolution: HashMap::new(),
+        };
+
+        match result.read_block()? {
+            Some((SECTION_HEADER_BLOCK, _)) => Ok(result),
+            _ => Err(invalid_data("pcapng file must start with a section header block")),
+        }
+    }
+
+    /// Information from the Section Header Block of the current section
+    pub fn section(&self) -> &CaptureSection {
+        &self.section
+    }
+
+    /// Interfaces declared so far in the current section
+    pub fn interfaces(&self) -> &[CaptureInterface] {
+        &self.interfaces
+    }
+
+    /// Address to host name mappings seen so far in the current section
+    pub fn name_resolution(&self) -> &HashMap<Vec<u8>, Vec<String>> {
+        &self.name_resolution
+    }
+
+    /// Link-layer header type of the first interface (0 if there is none yet)
+    pub fn link_type(&self) -> u32 {
+        self.interfaces.first().map_or(0, |interface| interface.link_type)
+    }
+
+    /// Read the next packet record, processing metadata blocks on the way
+    ///
+    /// Returns `Ok(None)` at a clean end of file.
+    pub fn next_record(&mut self) -> io::Result<Option<CaptureRecord>> {
+        loop {
+            let (block_type, body) = match self.read_block()? {
+                Some(block) => block,
+                None => return Ok(None),
+            };
+
+            match block_type {
+                ENHANCED_PACKET_BLOCK => return self.enhanced_packet(&body).map(Some),
+                SIMPLE_PACKET_BLOCK => return self.simple_packet(&body).map(Some),
+                PACKET_BLOCK => return self.obsolete_packet(&body).map(Some),
+                // Metadata blocks were already consumed by read_block()
+                _ => continue,
+            }
+        }
+    }
+
+    /// Read one block and return its type and body
+    ///
+    /// Section Header, Interface Description and Name Resolution blocks
+    /// update the reader state before being returned.
+    fn read_block(&mut self) -> io::Result<Option<(u32, Vec<u8>)>> {
+        let mut header = [0u8; 8];
+        if !read_fully(&mut self.reader, &mut header)? {
+            return Ok(None);
+        }
+
+        let block_type_bytes = [header[0], header[1], header[2], header[3]];
+
+        // The section header block type reads the same in both byte orders;
+        // its byte-order magic decides how the rest of the section is read
+        if block_type_bytes == SECTION_HEADER_BLOCK.to_le_bytes() {
+            let mut magic = [0u8; 4];
+            self.reader.read_exact(&mut magic)?;
+            self.big_endian = if magic == BYTE_ORDER_MAGIC.to_be_bytes() {
+                true
+            } else if magic == BYTE_ORDER_MAGIC.to_le_bytes() {
+                false
+            } else {
+                return Err(invalid_data("bad pcapng byte-order magic"));
+            };
+
+            let total_length = self.u32_from(&header[4..8]);
+            let body = self.read_body(total_length, 4)?;
+            self.section_header(&body)?;
+            return Ok(Some((SECTION_HEADER_BLOCK, body)));
+        }
+
+        let block_type = self.u32_from(&block_type_bytes);
+        let total_length = self.u32_from(&header[4..8]);
+        let body = self.read_body(total_length, 0)?;
+
+        match block_type {
+            INTERFACE_DESCRIPTION_BLOCK => self.interface_description(&body)?,
+            NAME_RESOLUTION_BLOCK => self.name_resolution_block(&body)?,
+            _ => {}
+        }
+
+        Ok(Some((block_type, body)))
+    }
+
+    /// Read the rest of a block body and its trailing length field
+    ///
+    /// `already_read` counts body bytes that were consumed before the call.
+    fn read_body(&mut self, total_length: u32, already_read: u32) -> io::Result<Vec<u8>> {
+        if total_length < 12 + already_read || !total_length.is_multiple_of(4) {
+            return Err(invalid_data("bad pcapng block length"));
+        }
+        if total_length > MAX_RECORD_LEN {
+            return Err(invalid_data("pcapng block length is implausibly large"));
+        }
+
+        let mut body = vec![0u8; (total_length - 12 - already_read) as usize];
+        self.reader.read_exact(&mut body)?;
+
+        let mut trailer = [0u8; 4];
+        self.reader.read_exact(&mut trailer)?;
+        if self.u32_from(&trailer) != total_length {
+            return Err(invalid_data("pcapng block lengths don't match"));
+        }
+
+        Ok(body)
+    }
+
+    /// Start a new section from a Section Header Block body
+    ///
+    /// `body` starts right after the byte-order magic.
+    fn section_header(&mut self, body: &[u8]) -> io::Result<()> {
+        // Version (2 + 2 bytes) and section length (8 bytes)
+        if body.len() < 12 {
+            return Err(invalid_data("truncated pcapng section header block"));
+        }
+
+        let mut section = CaptureSection {
+            major_version: self.u16_from(&body[0..2]),
+            minor_version: self.u16_from(&body[2..4]),
+            ..CaptureSection::default()
+        };
+        for option in self.options(&body[12..]) {
+            match option.code {
+                OPT_COMMENT => section.comments.push(option_string(option.value)),
+                SHB_HARDWARE => section.hardware = Some(option_string(option.value)),
+                SHB_OS => section.os = Some(option_string(option.value)),
+                SHB_USERAPPL => section.user_application = Some(option_string(option.value)),
+                _ => {}
+            }
+        }
+
+        // Interface IDs and name resolution records are only valid within
+        // their own section
+        self.section = section;
+        self.interfaces.clear();
+        self.name_resolution.clear();
+        Ok(())
+    }
+
+    /// Register an interface from an Interface Description Block body
+    fn interface_description(&mut self, body: &[u8]) -> io::Result<()> {
+        if body.len() < 8 {
+            return Err(invalid_data("truncated pcapng interface description block"));
+        }
+
+        let mut interface = CaptureInterface {
+            interface_id: self.interfaces.len() as u32,
+            link_type: u32::from(self.u16_from(&body[0..2])),
+            snaplen: self.u32_from(&body[4..8]),
+            name: None,
+            description: None,
+            filter: None,
+            os: None,
+            timestamp_resolution: DEFAULT_TS_RESOLUTION,
+            timestamp_offset: 0,
+            comments: Vec::new(),
+        };
+        for option in self.options(&body[8..]) {
+            match option.code {
+                OPT_COMMENT => interface.comments.push(option_string(option.value)),
+                IF_NAME => interface.name = Some(option_string(option.value)),
+                IF_DESCRIPTION => interface.description = Some(option_string(option.value)),
+                IF_OS => interface.os = Some(option_string(option.value)),
+                // The first byte is the filter type; 0 means a libpcap filter string
+                IF_FILTER if option.value.first() == Some(&0) => {
+                    interface.filter = Some(option_string(&option.value[1..]));
+                },
+                IF_TSRESOL if !option.value.is_empty() => {
+                    interface.timestamp_resolution = ts_resolution(option.value[0])?;
+                },
+                IF_TSOFFSET if option.value.len() >= 8 => {
+                    // A signed number of seconds, stored in two's complement
+                    interface.timestamp_offset = self.u64_from(&option.value[..8]) as i64;
+                },
+                _ => {}
+            }
+        }
+
+        self.interfaces.push(interface);
+        Ok(())
+    }
+
+    /// Record the address to name mappings of a Name Resolution Block body
+    fn name_resolution_block(&mut self, body: &[u8]) -> io::Result<()> {
+        let mut offset = 0;
+        while offset + 4 <= body.len() {
+            let record_type = self.u16_from(&body[offset..offset + 2]);
+            let length = usize::from(self.u16_from(&body[offset + 2..offset + 4]));
+            let value_start = offset + 4;
+            let value_end = value_start + length;
+            if record_type == NRB_RECORD_END || value_end > body.len() {
+                break;
+            }
+
+            let address_length = match record_type {
+                NRB_RECORD_IPV4 => 4,
+                NRB_RECORD_IPV6 => 16,
+                _ => 0,
+            };
+            if address_length > 0 && length > address_length {
+                let value = &body[value_start..value_end];
+                let names = value[address_length..]
+                    .split(|b| *b == 0)
+                    .filter(|name| !name.is_empty())
+                    .map(|name| String::from_utf8_lossy(name).into_owned());
+                self.name_resolution
+                    .entry(value[..address_length].to_vec())
+                    .or_default()
+                    .extend(names);
+            }
+
+            // Record values are padded to 32 bits
+            offset = value_start + padded(length);
+        }
+        Ok(())
+    }
+
+    /// Decode an Enhanced Packet Block body
+    fn enhanced_packet(&self, body: &[u8]) -> io::Result<CaptureRecord> {
+        if body.len() < 20 {
+            return Err(invalid_data("truncated pcapng enhanced packet block"));
+        }
+
+        let interface_id = self.u32_from(&body[0..4]);
+        let timestamp = (u64::from(self.u32_from(&body[4..8])) << 32)
+            | u64::from(self.u32_from(&body[8..12]));
+        let captured_length = self.u32_from(&body[12..16]) as usize;
+        let original_length = self.u32_from(&body[16..20]);
+
+        let data_end = 20 + captured_length;
+        if data_end > body.len() {
+            return Err(invalid_data("pcapng packet data exceeds its block"));
+        }
+
+        let mut record = self.packet_record(interface_id, timestamp, original_length, &body[20..data_end])?;
+        let options_start = (20 + padded(captured_length)).min(body.len());
+        for option in self.options(&body[options_start..]) {
+            match option.code {
+                OPT_COMMENT => record.comments.push(option_string(option.value)),
+                EPB_FLAGS if option.value.len() >= 4 => {
+                    record.flags = Some(self.u32_from(&option.value[..4]));
+                },
+                _ => {}
+            }
+        }
+        Ok(record)
+    }
+
+    /// Decode a Simple Packet Block body
+    ///
+    /// Simple packets always belong to the first interface and carry no
+    /// timestamp.
+    fn simple_packet(&self, body: &[u8]) -> io::Result<CaptureRecord> {
+        if body.len() < 4 {
+            return Err(invalid_data("truncated pcapng simple packet block"));
+        }
+
+        let original_length = self.u32_from(&body[0..4]);
+        let snaplen = self.interface(0)?.snaplen;
+
+        // The captured length isn't stored; it is the smallest of the
+        // original length, the snaplen and the block size
+        let mut captured_length = (original_length as usize).min(body.len() - 4);
+        if snaplen != 0 {
+            captured_length = captured_length.min(snaplen as usize);
+        }
+
+        self.packet_record(0, 0, original_length, &body[4..4 + captured_length])
+    }
+
+    /// Decode an obsolete Packet Block body
+    fn obsolete_packet(&self, body: &[u8]) -> io::Result<CaptureRecord> {
+        if body.len() < 20 {
+            return Err(invalid_data("truncated pcapng packet block"));
+        }
+
+        let interface_id = u32::from(self.u16_from(&body[0..2]));
+        let timestamp = (u64::from(self.u32_from(&body[4..8])) << 32)
+            | u64::from(self.u32_from(&body[8..12]));
+        let captured_length = self.u32_from(&body[12..16]) as usize;
+        let original_length = self.u32_from(&body[16..20]);
+
+        let data_end = 20 + captured_length;
+        if data_end > body.len() {
+            return Err(invalid_data("pcapng packet data exceeds its block"));
+        }
+
+        let mut record = self.packet_record(interface_id, timestamp, original_length, &body[20..data_end])?;
+        let options_start = (20 + padded(captured_length)).min(body.len());
+        for option in self.options(&body[options_start..]) {
+            if option.code == OPT_COMMENT {
+                record.comments.push(option_string(option.value));
+            }
+        }
+        Ok(record)
+    }
+
+    /// Build a record for a packet captured on the given interface
+    fn packet_record(
+        &self,
+        interface_id: u32,
+        timestamp: u64,
+        original_length: u32,
+        data: &[u8],
+    ) -> io::Result<CaptureRecord> {
+        let interface = self.interface(interface_id)?;
+
+        // Split the timestamp into seconds and nanoseconds using the
+        // resolution of the interface
+        let units = interface.timestamp_resolution;
+        let ts_sec = i64::try_from(timestamp / units)
+            .ok()
+            .and_then(|seconds| seconds.checked_add(interface.timestamp_offset))
+            .ok_or_else(|| invalid_data("pcapng packet timestamp is out of range"))?;
+        let ts_nsec = (u128::from(timestamp % units) * 1_000_000_000 / u128::from(units)) as u32;
+
+        Ok(CaptureRecord {
+            ts_sec,
+            ts_nsec,
+            original_length,
+            link_type: interface.link_type,
+            interface_id: Some(interface_id),
+            interface_name: interface.name.clone(),
+            comments: Vec::new(),
+            flags: None,
+            data: data.to_vec(),
+        })
+    }
+
+    /// Look up an interface of the current section
+    fn interface(&self, interface_id: u32) -> io::Result<&CaptureInterface> {
+        self.interfaces
+            .get(interface_id as usize)
+            .ok_or_else(|| invalid_data("pcapng packet refers to an unknown interface"))
+    }
+
+    /// Split an options area into its options, stopping at opt_endofopt
+    fn options<'a>(&self, mut area: &'a [u8]) -> Vec<BlockOption<'a>> {
+        let mut result = Vec::new();
+        while area.len() >= 4 {
+            let code = self.u16_from(&area[0..2]);
+            let length = usize::from(self.u16_from(&area[2..4]));
+            if code == OPT_END_OF_OPT || 4 + length > area.len() {
+                break;
+            }
+            result.push(BlockOption { code, value: &area[4..4 + length] });
+            area = &area[(4 + padded(length)).min(area.len())..];
+        }
+        result
+    }
+
+    /// Decode a u16 in the byte order of the current section
+    fn u16_from(&self, bytes: &[u8]) -> u16 {
+        let bytes = [bytes[0], bytes[1]];
+        if self.big_endian {
+            u16::from_be_bytes(bytes)
+        } else {
+            u16::from_le_bytes(bytes)
+        }
+    }
+
+    /// Decode a u32 in the byte order of the current section
+    fn u32_from(&self, bytes: &[u8]) -> u32 {
+        let bytes = [bytes[0], bytes[1], bytes[2], bytes[3]];
+        if self.big_endian {
+            u32::from_be_bytes(bytes)
+        } else {
+            u32::from_le_bytes(bytes)
+        }
+    }
+
+    /// Decode a u64 in the byte order of the current section
+    fn u64_from(&self, bytes: &[u8]) -> u64 {
+        let mut buf = [0u8; 8];
+        buf.copy_from_slice(&bytes[..8]);
+        if self.big_endian {
+            u64::from_be_bytes(buf)
+        } else {
+            u64::from_le_bytes(buf)
+        }
+    }
+}
+
+/// Decode an if_tsresol value into timestamp units per second
+///
+/// The high bit selects a power of two instead of a power of ten.
+fn ts_resolution(value: u8) -> io::Result<u64> {
+    let exponent = u32::from(value & 0x7f);
+    let units = if value & 0x80 != 0 {
+        1u64.checked_shl(exponent)
+    } else {
+        10u64.checked_pow(exponent)
+    };
+    units.ok_or_else(|| invalid_data("unsupported pcapng timestamp resolution"))
+}
+
+/// Decode a UTF-8 option value, dropping any trailing NUL padding
+fn option_string(value: &[u8]) -> String {
+    let end = value.iter().rposition(|b| *b != 0).map_or(0, |i| i + 1);
+    String::from_utf8_lossy(&value[..end]).into_owned()
+}
+
+/// Round a length up to the next multiple of 4
+fn padded(length: usize) -> usize {
+    (length + 3) & !3
+}
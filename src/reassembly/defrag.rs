@@ -0,0 +1,493 @@
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+use pyo3::prelude::*;
+use crate::flows::check_timeout;
+use crate::models::ParsedPacket;
+use crate::parsers::decode_frame;
+use crate::reassembly::fragments::{FragmentBuffer, OverlapPolicy};
+
+/// Identifies the fragments that belong to one datagram
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum DatagramKey {
+    // Source, destination, protocol and identification (RFC 791)
+    V4([u8; 4], [u8; 4], u8, u16),
+    // Source, destination and identification (RFC 8200, section 4.5)
+    V6([u8; 16], [u8; 16], u32),
+}
+
+/// Where the data of a fragment belongs within its datagram
+struct Fragment<'a> {
+    key: DatagramKey,
+    offset: usize,
+    // None if the capture cut the fragment short
+    data: Option<&'a [u8]>,
+    // Set on the fragment without the more fragments flag
+    last: bool,
+    // Most payload bytes the reassembled datagram can hold
+    max_len: usize,
+}
+
+/// A datagram waiting for more fragments
+struct PendingDatagram {
+    fragments: FragmentBuffer,
+    // Fragment at offset 0, whose headers the reassembled packet gets
+    first: Option<ParsedPacket>,
+    // Capture time of the first fragment received
+    started: f64,
+    // Tells the datagram apart from earlier ones with the same key
+    serial: u64,
+}
+
+/// Reassembles fragmented IPv4 and IPv6 datagrams from a stream of packets
+///
+/// Feed packets in capture order with push(). Packets that aren't
+/// fragments come straight back; fragments are held until their datagram
+/// is complete, and the fragment completing it returns the reassembled
+/// packet, decoded down to the transport header like any other:
+///
+///     defrag = Defragmenter()
+///     for packet in PcapReader("capture.pcap"):
+///         packet = defrag.push(packet)
+///         if packet is not None:
+///             ...
+///
+/// The reassembled packet keeps the link and IP headers of the first
+/// fragment, minus the IPv6 Fragment header, and the capture metadata of
+/// the fragment that completed it.
+///
+/// Datagrams still incomplete timeout seconds after their first fragment
+/// (judged by packet timestamps, not the wall clock) are dropped, as are
+/// the oldest datagrams once the held fragment data exceeds max_bytes.
+/// Fragments that can't be valid (cut short by the capture, misaligned, or
+/// reaching past the 65535 byte limit) are dropped too, as are fragments
+/// past the 8192nd of one datagram. The counters
+/// reassembled, timed_out, evicted and invalid keep track of all this.
+///
+/// The policy decides which data wins where fragments overlap: "first"
+/// keeps the data that arrived first, "last" the data that arrived last,
+/// "bsd" keeps earlier data unless the later fragment starts before it, and
+/// "linux" does the same but also lets a later fragment starting at the
+/// same offset win.
+///
+/// Args:
+///     policy: "first", "last", "bsd" or "linux" (default: "first")
+///     timeout: Seconds to wait for the rest of a datagram (default: 30)
+///     max_bytes: Most fragment data held at once, counting 64 bytes of
+///         overhead per fragment (default: 4 MiB)
+///
+/// Raises:
+///     ValueError: If the policy is unknown or the timeout is negative
+#[pyclass]
+pub struct Defragmenter {
+    policy: OverlapPolicy,
+    timeout: f64,
+    max_bytes: usize,
+    pending: HashMap<DatagramKey, PendingDatagram>,
+    // Datagrams in the order they were started, for timeouts and eviction;
+    // entries of datagrams that were completed since are skipped
+    order: VecDeque<(DatagramKey, u64)>,
+    next_serial: u64,
+    buffered_bytes: usize,
+
+    /// Number of datagrams reassembled
+    #[pyo3(get)]
+    pub reassembled: u64,
+
+    /// Number of datagrams dropped because the rest didn't arrive in time
+    #[pyo3(get)]
+    pub timed_out: u64,
+
+    /// Number of datagrams dropped to stay within max_bytes
+    #[pyo3(get)]
+    pub evicted: u64,
+
+    /// Number of fragments dropped because they can't be valid or their
+    /// datagram has too many fragments
+    #[pyo3(get)]
+    pub invalid: u64,
+}
+
+#[pymethods]
+impl Defragmenter {
+    #[new]
+    #[pyo3(signature = (policy = "first", timeout = 30.0, max_bytes = 4 * 1024 * 1024))]
+    pub fn new(policy: &str, timeout: f64, max_bytes: usize) -> PyResult<Self> {
+        Ok(Self {
+            policy: OverlapPolicy::parse(policy)?,
+            timeout: check_timeout("timeout", timeout)?,
+            max_bytes,
+            pending: HashMap::new(),
+            order: VecDeque::new(),
+            next_serial: 0,
+            buffered_bytes: 0,
+            reassembled: 0,
+            timed_out: 0,
+            evicted: 0,
+            invalid: 0,
+        })
+    }
+
+    /// Feed the next packet
+    ///
+    /// Args:
+    ///     packet: The next ParsedPacket in capture order
+    ///
+    /// Returns:
+    ///     ParsedPacket | None: The packet itself if it isn't a fragment, the
+    ///         reassembled packet if it completes a datagram, otherwise None
+    ///
+    /// Raises:
+    ///     ValueError: If the reassembled packet can't be serialized
+    pub fn push(&mut self, packet: Bound<'_, ParsedPacket>) -> PyResult<Option<Py<ParsedPacket>>> {
+        let py = packet.py();
+        let reassembled = {
+            let parsed = packet.borrow();
+            self.expire(parsed.timestamp());
+            match ipv4_fragment(&parsed).or_else(|| ipv6_fragment(&parsed)) {
+                None => return Ok(Some(packet.unbind())),
+                Some(fragment) => self.add(&parsed, fragment)?,
+            }
+        };
+        reassembled.map(|packet| Py::new(py, packet)).transpose()
+    }
+
+    /// Drop all datagrams waiting for fragments
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.order.clear();
+        self.buffered_bytes = 0;
+    }
+
+    /// Get the number of datagrams waiting for fragments
+    #[getter]
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    /// Get the number of fragment bytes held, including the overhead
+    /// counted per fragment
+    #[getter]
+    pub fn buffered_bytes(&self) -> usize {
+        self.buffered_bytes
+    }
+}
+
+impl Defragmenter {
+    /// Hold a fragment, returning the reassembled packet if it completes
+    /// its datagram
+    fn add(&mut self, packet: &ParsedPacket, fragment: Fragment<'_>) -> PyResult<Option<ParsedPacket>> {
+        let Fragment { key, offset, data, last, max_len } = fragment;
+        let data = match data {
+            // More fragments must follow on an 8 byte boundary
+            Some(data) if (last || data.len() % 8 == 0) && offset + data.len() <= max_len => data,
+            _ => {
+                self.invalid += 1;
+                return Ok(None);
+            },
+        };
+
+        let datagram = match self.pending.entry(key.clone()) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => {
+                let serial = self.next_serial;
+                self.next_serial += 1;
+                self.order.push_back((key.clone(), serial));
+                entry.insert(PendingDatagram {
+                    fragments: FragmentBuffer::new(),
+                    first: None,
+                    started: packet.timestamp(),
+                    serial,
+                })
+            },
+        };
+        let added = match datagram.fragments.insert(offset, data, last) {
+            Some(added) => added,
+            None => {
+                self.invalid += 1;
+                return Ok(None);
+            },
+        };
+        if offset == 0 && datagram.first.is_none() {
+            datagram.first = Some(packet.clone());
+        }
+        self.buffered_bytes += added;
+
+        if datagram.first.is_some() && datagram.fragments.is_complete() {
+            if let Some(datagram) = self.pending.remove(&key) {
+                self.buffered_bytes -= datagram.fragments.size;
+                self.reassembled += 1;
+                return self.rebuild(datagram, packet).map(Some);
+            }
+        }
+        while self.buffered_bytes > self.max_bytes && !self.order.is_empty() {
+            if self.drop_oldest() {
+                self.evicted += 1;
+            }
+        }
+        Ok(None)
+    }
+
+    /// Turn a complete datagram into a decoded packet
+    fn rebuild(&self, datagram: PendingDatagram, last: &ParsedPacket) -> PyResult<ParsedPacket> {
+        let payload = datagram.fragments.assemble(self.policy);
+        let mut first = match datagram.first {
+            Some(first) => first,
+            None => return Err(pyo3::exceptions::PyValueError::new_err("Datagram has no first fragment")),
+        };
+        if let Some(ipv4) = &mut first.ipv4 {
+            ipv4.set_more_fragments(false);
+            ipv4.set_fragment_offset(0)?;
+        }
+        if let Some(ipv6) = &mut first.ipv6 {
+            ipv6.remove_fragment_header();
+        }
+        first.set_payload(payload);
+
+        let bytes = first.to_bytes(true)?;
+        let mut packet = decode_frame(&bytes, first.link_type);
+        packet.link_type = first.link_type;
+        packet.interface_id = first.interface_id;
+        packet.interface_name = first.interface_name;
+        packet.set_capture_info(last.ts_sec, last.ts_nsec, bytes.len() as u32, last.frame_index);
+        Ok(packet)
+    }
+
+    /// Drop datagrams whose first fragment is more than timeout seconds
+    /// older than now
+    fn expire(&mut self, now: f64) {
+        while let Some((key, serial)) = self.order.front() {
+            if let Some(datagram) = self.pending.get(key).filter(|datagram| datagram.serial == *serial) {
+                if now - datagram.started <= self.timeout {
+                    break;
+                }
+                self.timed_out += 1;
+            }
+            self.drop_oldest();
+        }
+    }
+
+    /// Forget the oldest datagram, returning whether it was still pending
+    fn drop_oldest(&mut self) -> bool {
+        let (key, serial) = match self.order.pop_front() {
+            Some(entry) => entry,
+            None => return false,
+        };
+        match self.pending.entry(key) {
+            Entry::Occupied(entry) if entry.get().serial == serial => {
+                self.buffered_bytes -= entry.remove().fragments.size;
+                true
+            },
+            _ => false,
+        }
+    }
+}
+
+/// Describe the fragment carried by a packet, if it is an IPv4 fragment
+fn ipv4_fragment(packet: &ParsedPacket) -> Option<Fragment<'_>> {
+    let ipv4 = packet.ipv4.as_ref()?;
+    if !ipv4.more_fragments() && ipv4.fragment_offset() == 0 {
+        return None;
+    }
+    Some(Fragment {
+        key: DatagramKey::V4(ipv4.source(), ipv4.destination(), ipv4.protocol(), ipv4.identification()),
+        offset: usize::from(ipv4.fragment_offset()) * 8,
+        data: packet.ip_payload().get(..usize::from(ipv4.payload_length())),
+        last: !ipv4.more_fragments(),
+        max_len: usize::from(u16::MAX) - usize::from(ipv4.header_length()),
+    })
+}
+
+/// Describe the fragment carried by a packet, if it is an IPv6 fragment
+///
+/// The fragment data is what follows the Fragment header, which is the
+/// last extension header the decoder reads.
+fn ipv6_fragment(packet: &ParsedPacket) -> Option<Fragment<'_>> {
+    let ipv6 = packet.ipv6.as_ref()?;
+    let fragment = ipv6.fragment_header()?;
+    let extensions_len = ipv6.header_length() - 40;
+    let data_len = usize::from(ipv6.payload_length()).checked_sub(extensions_len);
+    Some(Fragment {
+        key: DatagramKey::V6(ipv6.source(), ipv6.destination(), fragment.identification),
+        offset: usize::from(fragment.fragment_offset) * 8,
+        data: data_len.and_then(|data_len| packet.ip_payload().get(..data_len)),
+        last: !fragment.more_fragments,
+        // The extension headers before the Fragment header stay in the
+        // reassembled packet
+        max_len: usize::from(u16::MAX) - (extensions_len - 8),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// UDP datagram from port 1000 to 2000 carrying 24 bytes, without a
+    /// checksum
+    fn udp_datagram() -> Vec<u8> {
+        let mut datagram = vec![0x03, 0xe8, 0x07, 0xd0, 0, 32, 0, 0];
+        datagram.extend_from_slice(b"abcdefghijklmnopqrstuvwx");
+        datagram
+    }
+
+    /// Decode an Ethernet frame carrying an IPv4 fragment captured at the
+    /// given second
+    fn fragment(identification: u16, offset: u16, more_fragments: bool, data: &[u8], ts_sec: i64) -> ParsedPacket {
+        let mut ip = etherparse::Ipv4Header::new(data.len() as u16, 64, 17, [10, 0, 0, 1], [10, 0, 0, 2]);
+        ip.identification = identification;
+        ip.more_fragments = more_fragments;
+        ip.fragments_offset = offset / 8;
+        let ethernet = etherparse::Ethernet2Header {
+            source: [2, 0, 0, 0, 0, 1],
+            destination: [2, 0, 0, 0, 0, 2],
+            ether_type: 0x0800,
+        };
+        let mut frame = Vec::new();
+        ethernet.write(&mut frame).unwrap();
+        ip.write(&mut frame).unwrap();
+        frame.extend_from_slice(data);
+        let mut packet = decode_frame(&frame, None);
+        packet.set_capture_info(ts_sec, 0, frame.len() as u32, 0);
+        packet
+    }
+
+    /// Decode an Ethernet frame carrying an IPv6 fragment, behind a
+    /// Hop-by-Hop Options header with a PadN option
+    fn fragment_v6(identification: u32, offset: u16, more_fragments: bool, data: &[u8]) -> ParsedPacket {
+        // Hop-by-Hop Options: next header Fragment, 8 bytes long
+        let mut extensions = vec![44, 0, 1, 4, 0, 0, 0, 0];
+        // Fragment: next header UDP
+        extensions.extend_from_slice(&[17, 0]);
+        extensions.extend_from_slice(&(offset | u16::from(more_fragments)).to_be_bytes());
+        extensions.extend_from_slice(&identification.to_be_bytes());
+        let ip = etherparse::Ipv6Header {
+            payload_length: (extensions.len() + data.len()) as u16,
+            next_header: 0,
+            hop_limit: 64,
+            source: [0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1],
+            destination: [0xfe, 0x80, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2],
+            ..Default::default()
+        };
+        let ethernet = etherparse::Ethernet2Header {
+            source: [2, 0, 0, 0, 0, 1],
+            destination: [2, 0, 0, 0, 0, 2],
+            ether_type: 0x86dd,
+        };
+        let mut frame = Vec::new();
+        ethernet.write(&mut frame).unwrap();
+        ip.write(&mut frame).unwrap();
+        frame.extend_from_slice(&extensions);
+        frame.extend_from_slice(data);
+        decode_frame(&frame, None)
+    }
+
+    /// Feed a fragment the way push() does
+    fn feed(defrag: &mut Defragmenter, packet: &ParsedPacket) -> Option<ParsedPacket> {
+        defrag.expire(packet.timestamp());
+        let fragment = ipv4_fragment(packet)
+            .or_else(|| ipv6_fragment(packet))
+            .expect("packet is a fragment");
+        defrag.add(packet, fragment).unwrap()
+    }
+
+    #[test]
+    fn whole_packets_are_not_fragments() {
+        assert!(ipv4_fragment(&fragment(1, 0, false, &udp_datagram(), 0)).is_none());
+    }
+
+    #[test]
+    fn fragments_are_reassembled_in_any_order() {
+        let datagram = udp_datagram();
+        let mut defrag = Defragmenter::new("first", 30.0, 4096).unwrap();
+        assert!(feed(&mut defrag, &fragment(7, 16, false, &datagram[16..], 1)).is_none());
+        assert_eq!(defrag.pending(), 1);
+        let packet = feed(&mut defrag, &fragment(7, 0, true, &datagram[..16], 2)).expect("reassembled");
+
+        let ipv4 = packet.ipv4.as_ref().unwrap();
+        assert!(!ipv4.more_fragments());
+        assert_eq!(ipv4.fragment_offset(), 0);
+        assert_eq!(ipv4.identification(), 7);
+        assert_eq!(packet.udp.as_ref().map(|udp| udp.source_port()), Some(1000));
+        assert_eq!(packet.payload, &datagram[8..]);
+        assert_eq!(packet.ts_sec, 2);
+        assert_eq!((defrag.reassembled, defrag.pending(), defrag.buffered_bytes()), (1, 0, 0));
+    }
+
+    #[test]
+    fn datagrams_are_kept_apart_by_identification() {
+        let datagram = udp_datagram();
+        let mut defrag = Defragmenter::new("first", 30.0, 4096).unwrap();
+        assert!(feed(&mut defrag, &fragment(1, 0, true, &datagram[..16], 0)).is_none());
+        assert!(feed(&mut defrag, &fragment(2, 16, false, &datagram[16..], 0)).is_none());
+        assert_eq!(defrag.pending(), 2);
+    }
+
+    #[test]
+    fn incomplete_datagrams_time_out() {
+        let datagram = udp_datagram();
+        let mut defrag = Defragmenter::new("first", 30.0, 4096).unwrap();
+        feed(&mut defrag, &fragment(1, 0, true, &datagram[..16], 0));
+        feed(&mut defrag, &fragment(2, 0, true, &datagram[..16], 20));
+        defrag.expire(30.0);
+        assert_eq!((defrag.timed_out, defrag.pending()), (0, 2));
+        defrag.expire(31.0);
+        assert_eq!((defrag.timed_out, defrag.pending()), (1, 1));
+        // The rest of the timed out datagram starts a new one
+        assert!(feed(&mut defrag, &fragment(1, 16, false, &datagram[16..], 31)).is_none());
+        assert_eq!(defrag.pending(), 2);
+    }
+
+    #[test]
+    fn oldest_datagrams_are_evicted_beyond_max_bytes() {
+        let datagram = udp_datagram();
+        let mut defrag = Defragmenter::new("first", 30.0, 100).unwrap();
+        feed(&mut defrag, &fragment(1, 0, true, &datagram[..16], 0));
+        feed(&mut defrag, &fragment(2, 0, true, &datagram[..16], 0));
+        assert_eq!((defrag.evicted, defrag.pending()), (1, 1));
+        // 16 bytes of data and 64 of overhead
+        assert_eq!(defrag.buffered_bytes(), 80);
+        // Datagram 2 is still there
+        assert!(feed(&mut defrag, &fragment(2, 16, false, &datagram[16..], 0)).is_some());
+    }
+
+    #[test]
+    fn impossible_fragments_are_invalid() {
+        let mut defrag = Defragmenter::new("first", 30.0, 4096).unwrap();
+        // More fragments follow, but not on an 8 byte boundary
+        feed(&mut defrag, &fragment(1, 0, true, &[0; 12], 0));
+        // Reaching past the largest possible datagram
+        feed(&mut defrag, &fragment(1, 65512, false, &[0; 16], 0));
+        assert_eq!((defrag.invalid, defrag.pending()), (2, 0));
+    }
+
+    #[test]
+    fn ipv6_fragments_are_reassembled_without_the_fragment_header() {
+        let datagram = udp_datagram();
+        let mut defrag = Defragmenter::new("first", 30.0, 4096).unwrap();
+        assert!(feed(&mut defrag, &fragment_v6(9, 16, false, &datagram[16..])).is_none());
+        // Another identification is another datagram
+        assert!(feed(&mut defrag, &fragment_v6(10, 0, true, &datagram[..16])).is_none());
+        let packet = feed(&mut defrag, &fragment_v6(9, 0, true, &datagram[..16])).expect("reassembled");
+
+        let ipv6 = packet.ipv6.as_ref().unwrap();
+        assert_eq!(ipv6.next_header(), 0);
+        assert_eq!(ipv6.extensions().len(), 1);
+        assert!(ipv6.fragment_header().is_none());
+        assert_eq!(ipv6.upper_layer_protocol(), 17);
+        assert_eq!(ipv6.header_length(), 48);
+        assert_eq!(usize::from(ipv6.payload_length()), 8 + datagram.len());
+        assert_eq!(packet.udp.as_ref().map(|udp| udp.source_port()), Some(1000));
+        assert_eq!(packet.payload, &datagram[8..]);
+        assert_eq!((defrag.reassembled, defrag.pending()), (1, 1));
+    }
+
+    #[test]
+    fn ipv6_extensions_before_the_fragment_header_count_against_the_size_limit() {
+        let mut defrag = Defragmenter::new("first", 30.0, 4096).unwrap();
+        // Would end at 65528, but the Hop-by-Hop header takes 8 of the
+        // 65535 bytes
+        feed(&mut defrag, &fragment_v6(1, 65520, false, &[0; 8]));
+        assert_eq!(defrag.invalid, 1);
+        feed(&mut defrag, &fragment_v6(1, 65512, false, &[0; 8]));
+        assert_eq!((defrag.invalid, defrag.pending()), (1, 1));
+    }
+}
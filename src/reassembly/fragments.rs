@@ -0,0 +1,239 @@
+use std::collections::BTreeMap;
+use pyo3::prelude::*;
+
+/// How data of overlapping fragments is resolved
+///
+/// Operating systems disagree on which copy wins when fragments overlap,
+/// which attackers use to show an IDS different data than the target host
+/// sees. The policies follow the target-based reassembly models of Snort
+/// and Suricata.
+#[derive(Clone, Copy, PartialEq)]
+pub(crate) enum OverlapPolicy {
+    // Keep the data that arrived first
+    First,
+    // Let data that arrives later overwrite earlier data
+    Last,
+    // Keep earlier data, unless the later fragment starts before it
+    Bsd,
+    // Keep earlier data, unless the later fragment starts before it or at
+    // the same offset
+    Linux,
+}
+
+impl OverlapPolicy {
+    pub(crate) fn parse(policy: &str) -> PyResult<Self> {
+        match policy {
+            "first" => Ok(Self::First),
+            "last" => Ok(Self::Last),
+            "bsd" => Ok(Self::Bsd),
+            "linux" => Ok(Self::Linux),
+            _ => Err(pyo3::exceptions::PyValueError::new_err(format!(
+                "Invalid overlap policy: {:?} (expected \"first\", \"last\", \"bsd\" or \"linux\")",
+                policy
+            ))),
+        }
+    }
+
+    /// Whether a fragment replaces the bytes it shares with an earlier one
+    fn overwrites(self, offset: usize, earlier_offset: usize) -> bool {
+        match self {
+            Self::First => false,
+            Self::Last => true,
+            Self::Bsd => offset < earlier_offset,
+            Self::Linux => offset <= earlier_offset,
+        }
+    }
+}
+
+/// Bytes counted for every held fragment on top of its data, so floods of
+/// tiny or empty fragments count against the buffer limit too
+const FRAGMENT_OVERHEAD: usize = 64;
+
+/// Most fragments held for one datagram; 8192 fragments of 8 bytes already
+/// cover the largest possible payload
+const MAX_FRAGMENTS: usize = 8192;
+
+/// The fragments of one datagram received so far
+pub(crate) struct FragmentBuffer {
+    // Offset and data of each fragment, in arrival order
+    fragments: Vec<(usize, Vec<u8>)>,
+    // Payload ranges the fragments cover, merged, as start and end
+    covered: BTreeMap<usize, usize>,
+    // Length of the reassembled payload, known once the last fragment arrived
+    total_len: Option<usize>,
+    // Number of bytes held, including the per-fragment overhead
+    pub(crate) size: usize,
+}
+
+impl FragmentBuffer {
+    pub(crate) fn new() -> Self {
+        Self {
+            fragments: Vec::new(),
+            covered: BTreeMap::new(),
+            total_len: None,
+            size: 0,
+        }
+    }
+
+    /// Add a fragment, given where its data starts in the payload
+    ///
+    /// The last fragment sets the payload length; data of other fragments
+    /// reaching past it is dropped when the payload is assembled. Returns
+    /// the number of bytes now held in addition, or None if the datagram
+    /// already has MAX_FRAGMENTS fragments and this one is dropped.
+    pub(crate) fn insert(&mut self, offset: usize, data: &[u8], last: bool) -> Option<usize> {
+        if self.fragments.len() >= MAX_FRAGMENTS {
+            return None;
+        }
+        if last && self.total_len.is_none() {
+            self.total_len = Some(offset + data.len());
+        }
+        self.cover(offset, offset + data.len());
+        self.fragments.push((offset, data.to_vec()));
+        let added = data.len() + FRAGMENT_OVERHEAD;
+        self.size += added;
+        Some(added)
+    }
+
+    /// Check whether the fragments cover the whole payload
+    pub(crate) fn is_complete(&self) -> bool {
+        match (self.total_len, self.covered.first_key_value()) {
+            (Some(total_len), Some((&0, &end))) => end >= total_len,
+            (Some(total_len), None) => total_len == 0,
+            _ => false,
+        }
+    }
+
+    /// Add a range to the covered ranges, merging it with the ones it
+    /// overlaps or touches
+    fn cover(&mut self, mut start: usize, mut end: usize) {
+        if start == end {
+            return;
+        }
+        if let Some((&before, &before_end)) = self.covered.range(..=start).next_back() {
+            if before_end >= start {
+                start = before;
+                end = end.max(before_end);
+            }
+        }
+        let merged: Vec<usize> = self.covered.range(start..=end).map(|(&from, _)| from).collect();
+        for from in merged {
+            if let Some(to) = self.covered.remove(&from) {
+                end = end.max(to);
+            }
+        }
+        self.covered.insert(start, end);
+    }
+
+    /// Put the payload together, resolving overlaps with the given policy
+    pub(crate) fn assemble(&self, policy: OverlapPolicy) -> Vec<u8> {
+        let total_len = self.total_len.unwrap_or(0);
+        let mut payload = vec![0; total_len];
+        // Offset of the fragment each byte was taken from
+        let mut sources: Vec<Option<usize>> = vec![None; total_len];
+        for (offset, data) in &self.fragments {
+            let end = (offset + data.len()).min(total_len);
+            for position in *offset..end {
+                let take = match sources[position] {
+                    None => true,
+                    Some(earlier_offset) => policy.overwrites(*offset, earlier_offset),
+                };
+                if take {
+                    payload[position] = data[position - offset];
+                    sources[position] = Some(*offset);
+                }
+            }
+        }
+        payload
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Original data at 0..16, then an overlapping fragment at 8..24 and
+    /// one at 0..8 repeating the start with different bytes
+    fn overlapping() -> FragmentBuffer {
+        let mut buffer = FragmentBuffer::new();
+        buffer.insert(0, b"AAAAAAAAAAAAAAAA", false);
+        buffer.insert(8, b"BBBBBBBBBBBBBBBB", true);
+        buffer.insert(0, b"CCCCCCCC", false);
+        buffer
+    }
+
+    #[test]
+    fn first_keeps_earlier_data() {
+        assert_eq!(overlapping().assemble(OverlapPolicy::First), b"AAAAAAAAAAAAAAAABBBBBBBB");
+    }
+
+    #[test]
+    fn last_keeps_later_data() {
+        assert_eq!(overlapping().assemble(OverlapPolicy::Last), b"CCCCCCCCBBBBBBBBBBBBBBBB");
+    }
+
+    #[test]
+    fn bsd_only_lets_fragments_starting_before_win() {
+        assert_eq!(overlapping().assemble(OverlapPolicy::Bsd), b"AAAAAAAAAAAAAAAABBBBBBBB");
+
+        let mut buffer = FragmentBuffer::new();
+        buffer.insert(8, b"AAAAAAAA", true);
+        buffer.insert(0, b"BBBBBBBBBBBBBBBB", false);
+        assert_eq!(buffer.assemble(OverlapPolicy::Bsd), b"BBBBBBBBBBBBBBBB");
+    }
+
+    #[test]
+    fn linux_also_lets_fragments_at_the_same_offset_win() {
+        assert_eq!(overlapping().assemble(OverlapPolicy::Linux), b"CCCCCCCCAAAAAAAABBBBBBBB");
+    }
+
+    #[test]
+    fn data_past_the_last_fragment_is_dropped() {
+        let mut buffer = FragmentBuffer::new();
+        buffer.insert(0, b"AAAAAAAAAAAAAAAA", false);
+        buffer.insert(8, b"BB", true);
+        assert!(buffer.is_complete());
+        assert_eq!(buffer.assemble(OverlapPolicy::First), b"AAAAAAAAAA");
+    }
+
+    #[test]
+    fn complete_once_the_fragments_join_up() {
+        let mut buffer = FragmentBuffer::new();
+        buffer.insert(16, b"CCCC", true);
+        assert!(!buffer.is_complete());
+        buffer.insert(0, b"AAAAAAAA", false);
+        assert!(!buffer.is_complete());
+        buffer.insert(8, b"BBBBBBBB", false);
+        assert!(buffer.is_complete());
+        assert_eq!(buffer.covered.len(), 1);
+    }
+
+    #[test]
+    fn incomplete_without_the_last_fragment() {
+        let mut buffer = FragmentBuffer::new();
+        buffer.insert(0, b"AAAAAAAA", false);
+        buffer.insert(8, b"BBBBBBBB", false);
+        assert!(!buffer.is_complete());
+    }
+
+    #[test]
+    fn gap_keeps_datagram_incomplete() {
+        let mut buffer = FragmentBuffer::new();
+        buffer.insert(0, b"AAAAAAAA", false);
+        buffer.insert(16, b"CCCC", true);
+        assert!(!buffer.is_complete());
+        assert_eq!(buffer.covered.len(), 2);
+    }
+
+    #[test]
+    fn insert_counts_overhead_and_caps_fragments() {
+        let mut buffer = FragmentBuffer::new();
+        assert_eq!(buffer.insert(0, b"AAAAAAAA", false), Some(8 + FRAGMENT_OVERHEAD));
+        for _ in 1..MAX_FRAGMENTS {
+            assert!(buffer.insert(0, b"", false).is_some());
+        }
+        assert_eq!(buffer.insert(8, b"BBBB", true), None);
+        assert_eq!(buffer.size, 8 + MAX_FRAGMENTS * FRAGMENT_OVERHEAD);
+        assert!(!buffer.is_complete());
+    }
+}
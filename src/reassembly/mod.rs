@@ -0,0 +1,13 @@
+//! Reassembly of data split across packets
+//!
+//! This module contains classes that put fragmented IP datagrams and TCP
+//! byte streams back together from a stream of parsed packets.
+
+// Declare submodules
+mod defrag;
+mod fragments;
+mod tcp;
+
+// Re-export reassembly classes
+pub use defrag::Defragmenter;
+pub use tcp::{StreamChunk, TcpReassembler};
@@ -0,0 +1,648 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use pyo3::prelude::*;
+use crate::flows::{check_timeout, Endpoints, SWEEP_INTERVAL};
+use crate::models::ParsedPacket;
+
+/// A piece of a reassembled TCP byte stream
+///
+/// Chunks of one direction follow each other without overlap: offset is
+/// where the chunk starts within the bytes that side sent. A "data" chunk
+/// carries bytes, a "gap" chunk stands for length bytes that were never
+/// captured, and an "end" chunk closes the direction after a FIN, a reset,
+/// a timeout or a flush.
+#[pyclass]
+#[derive(Clone)]
+pub struct StreamChunk {
+    /// "data", "gap" or "end"
+    #[pyo3(get)]
+    pub kind: &'static str,
+
+    /// Whether the client sent the bytes (False for the server)
+    #[pyo3(get)]
+    pub from_client: bool,
+
+    /// Address of the client, the endpoint that opened the connection
+    #[pyo3(get)]
+    pub client: Vec<u8>,
+
+    /// Port of the client
+    #[pyo3(get)]
+    pub client_port: u16,
+
+    /// Address of the server
+    #[pyo3(get)]
+    pub server: Vec<u8>,
+
+    /// Port of the server
+    #[pyo3(get)]
+    pub server_port: u16,
+
+    /// Position of the chunk within the bytes sent in its direction
+    #[pyo3(get)]
+    pub offset: u64,
+
+    /// Length of the data or of the gap (0 for "end" chunks)
+    #[pyo3(get)]
+    pub length: u64,
+
+    /// Bytes of a "data" chunk (empty for other kinds)
+    #[pyo3(get)]
+    pub data: Vec<u8>,
+
+    /// Capture timestamp of the packet that completed the chunk
+    #[pyo3(get)]
+    pub timestamp: f64,
+}
+
+#[pymethods]
+impl StreamChunk {
+    pub fn __repr__(&self) -> String {
+        format!(
+            "StreamChunk(kind={:?}, from_client={}, offset={}, length={})",
+            self.kind,
+            if self.from_client { "True" } else { "False" },
+            self.offset,
+            self.length
+        )
+    }
+}
+
+/// Something that happened to one direction of a stream
+enum Event {
+    Data(u64, Vec<u8>),
+    Gap(u64, u64),
+    End(u64),
+}
+
+/// The bytes one side of a connection sent
+#[derive(Default)]
+struct HalfStream {
+    // Sequence number of the next byte to deliver, once known
+    next_seq: Option<u32>,
+    // Stream offset of the next byte to deliver
+    offset: u64,
+    // Segments that arrived ahead of the next byte, by stream offset
+    pending: BTreeMap<u64, Vec<u8>>,
+    pending_bytes: usize,
+    // Stream offset of the sender's FIN
+    fin: Option<u64>,
+    ended: bool,
+}
+
+impl HalfStream {
+    /// Get the stream offset of a sequence number, which is negative for
+    /// bytes before the ones delivered already
+    ///
+    /// Sequence numbers are compared modulo 2^32, so this holds across
+    /// wraparound as long as the window stays below 2 GiB.
+    fn position(&mut self, seq: u32) -> i64 {
+        let next_seq = *self.next_seq.get_or_insert(seq);
+        self.offset as i64 + i64::from(seq.wrapping_sub(next_seq) as i32)
+    }
+
+    /// Store a segment starting at the given stream offset
+    ///
+    /// Only the bytes not delivered or held already are kept, so
+    /// retransmissions and overlaps keep the data that arrived first and
+    /// held segments never overlap.
+    fn insert(&mut self, position: i64, data: &[u8]) {
+        let skip = (self.offset as i64 - position).max(0) as usize;
+        if self.ended || skip >= data.len() {
+            return;
+        }
+        let start = position.max(self.offset as i64) as u64;
+        let end = start + (data.len() - skip) as u64;
+        let data = &data[skip..];
+
+        // Cut the segment around the held ones it overlaps
+        let mut cursor = self.pending
+            .range(..start)
+            .next_back()
+            .map_or(start, |(&held, bytes)| start.max(held + bytes.len() as u64));
+        let mut uncovered = Vec::new();
+        for (&held, bytes) in self.pending.range(start..end) {
+            if held > cursor {
+                uncovered.push((cursor, held));
+            }
+            cursor = cursor.max(held + bytes.len() as u64);
+        }
+        if cursor < end {
+            uncovered.push((cursor, end));
+        }
+        for (from, to) in uncovered {
+            self.pending.insert(from, data[(from - start) as usize..(to - start) as usize].to_vec());
+            self.pending_bytes += (to - from) as usize;
+        }
+    }
+
+    /// Deliver the segments that continue the stream, optionally skipping
+    /// over missing data
+    fn deliver(&mut self, skip_gaps: bool, events: &mut Vec<Event>) {
+        while let Some((&start, _)) = self.pending.first_key_value() {
+            if start > self.offset {
+                if !skip_gaps {
+                    break;
+                }
+                events.push(Event::Gap(self.offset, start - self.offset));
+                self.advance(start - self.offset);
+            }
+            if let Some((start, data)) = self.pending.pop_first() {
+                self.pending_bytes -= data.len();
+                let skip = (self.offset - start) as usize;
+                if skip < data.len() {
+                    // Pieces of one segment come back together
+                    match events.last_mut() {
+                        Some(Event::Data(offset, bytes)) if *offset + bytes.len() as u64 == self.offset => {
+                            bytes.extend_from_slice(&data[skip..]);
+                        },
+                        _ => events.push(Event::Data(self.offset, data[skip..].to_vec())),
+                    }
+                    self.advance((data.len() - skip) as u64);
+                }
+            }
+        }
+        if self.fin.is_some_and(|fin| self.offset >= fin) {
+            self.end(events);
+        }
+    }
+
+    /// Deliver everything held and end the direction
+    fn close(&mut self, events: &mut Vec<Event>) {
+        self.deliver(true, events);
+        if let Some(fin) = self.fin.filter(|fin| *fin > self.offset) {
+            events.push(Event::Gap(self.offset, fin - self.offset));
+            self.advance(fin - self.offset);
+        }
+        self.end(events);
+    }
+
+    fn end(&mut self, events: &mut Vec<Event>) {
+        if !self.ended {
+            self.ended = true;
+            self.pending.clear();
+            self.pending_bytes = 0;
+            events.push(Event::End(self.offset));
+        }
+    }
+
+    fn advance(&mut self, length: u64) {
+        self.offset += length;
+        self.next_seq = self.next_seq.map(|seq| seq.wrapping_add(length as u32));
+    }
+}
+
+/// Both directions of one connection
+struct Stream {
+    // Endpoints of packets sent by the client
+    client: Endpoints,
+    // Sent by the client and by the server
+    halves: [HalfStream; 2],
+    last_seen: f64,
+}
+
+impl Stream {
+    /// Check whether both directions ended; the stream is then only kept
+    /// until it times out, to ignore late segments
+    fn is_closed(&self) -> bool {
+        self.halves.iter().all(|half| half.ended)
+    }
+}
+
+/// Reconstructs the byte streams of TCP connections
+///
+/// Feed packets in capture order with add(). Segments are put in sequence
+/// order; retransmitted and overlapping bytes are delivered once (the copy
+/// that arrived first wins), and sequence numbers may wrap around. Data is
+/// delivered as StreamChunks, either to the on_chunk callback or, without
+/// one, collected by chunks():
+///
+///     reassembler = TcpReassembler()
+///     for packet in PcapReader("capture.pcap"):
+///         reassembler.add(packet)
+///         for chunk in reassembler.chunks():
+///             if chunk.kind == "data" and chunk.from_client:
+///                 requests[chunk.client_port] += chunk.data
+///     reassembler.flush()
+///
+/// The client is the side that sent the SYN, or else the side that sent
+/// the first packet seen. When a direction holds more than max_buffer
+/// bytes waiting for missing data, or its connection is reset, times out
+/// or is flushed, the missing bytes are given up on and show up as "gap"
+/// chunks. Timeouts follow packet timestamps, not the wall clock.
+/// Segments arriving after both directions ended, like the final ACK, are
+/// ignored until the connection times out or a new SYN reuses it.
+///
+/// Args:
+///     on_chunk: Callable receiving each StreamChunk (default: None, keep
+///         them for chunks())
+///     max_buffer: Most out-of-order bytes held per direction (default:
+///         1 MiB)
+///     timeout: Seconds without packets after which a connection is
+///         closed (default: 300)
+///
+/// Raises:
+///     ValueError: If the timeout is negative
+#[pyclass]
+pub struct TcpReassembler {
+    on_chunk: Option<PyObject>,
+    max_buffer: usize,
+    timeout: f64,
+    streams: HashMap<Endpoints, Stream>,
+    // Chunks not taken with chunks() yet
+    chunks: VecDeque<StreamChunk>,
+    next_sweep: f64,
+}
+
+#[pymethods]
+impl TcpReassembler {
+    #[new]
+    #[pyo3(signature = (on_chunk = None, max_buffer = 1024 * 1024, timeout = 300.0))]
+    pub fn new(on_chunk: Option<PyObject>, max_buffer: usize, timeout: f64) -> PyResult<Self> {
+        Ok(Self {
+            on_chunk,
+            max_buffer,
+            timeout: check_timeout("timeout", timeout)?,
+            streams: HashMap::new(),
+            chunks: VecDeque::new(),
+            next_sweep: f64::NEG_INFINITY,
+        })
+    }
+
+    /// Add the segment carried by a packet to its stream
+    ///
+    /// Args:
+    ///     packet: The next ParsedPacket in capture order
+    ///
+    /// Returns:
+    ///     bool: True if the packet was a TCP segment, False otherwise
+    ///
+    /// Raises:
+    ///     Exception: Whatever the on_chunk callback raises
+    pub fn add(&mut self, py: Python<'_>, packet: &ParsedPacket) -> PyResult<bool> {
+        let (tcp, endpoints) = match (&packet.tcp, Endpoints::of(packet)) {
+            (Some(tcp), Some(endpoints)) => (tcp, endpoints),
+            _ => return Ok(false),
+        };
+        let now = packet.timestamp();
+        if now >= self.next_sweep {
+            self.sweep(now);
+            self.next_sweep = now + SWEEP_INTERVAL;
+        }
+
+        let key = endpoints.key();
+        let data = packet.ip_payload();
+        let reused = match self.streams.get_mut(&key) {
+            // Late segments of a connection that ended, such as the last
+            // ACK, are ignored; a new SYN starts the next connection
+            Some(stream) if stream.is_closed() => {
+                if !tcp.syn() || tcp.ack() {
+                    stream.last_seen = stream.last_seen.max(now);
+                    return Ok(true);
+                }
+                true
+            },
+            Some(_) => false,
+            // Without a SYN or data there is nothing to start a stream with
+            None if !tcp.syn() && data.is_empty() => return Ok(true),
+            None => false,
+        };
+        if reused {
+            self.streams.remove(&key);
+        }
+        let stream = self.streams.entry(key.clone()).or_insert_with(|| Stream {
+            // A SYN-ACK comes from the server
+            client: if tcp.syn() && tcp.ack() { endpoints.reversed() } else { endpoints.clone() },
+            halves: Default::default(),
+            last_seen: now,
+        });
+        stream.last_seen = stream.last_seen.max(now);
+        let from_client = endpoints == stream.client;
+        let half = &mut stream.halves[usize::from(!from_client)];
+
+        // The SYN takes up one sequence number before the data
+        let seq = tcp.sequence_number().wrapping_add(u32::from(tcp.syn()));
+        let position = half.position(seq);
+        half.insert(position, data);
+        if tcp.fin() && half.fin.is_none() {
+            half.fin = Some((position + data.len() as i64).max(0) as u64);
+        }
+
+        let mut events = Vec::new();
+        half.deliver(false, &mut events);
+        if half.pending_bytes > self.max_buffer {
+            half.deliver(true, &mut events);
+        }
+        let mut chunks = stream_chunks(stream, from_client, events, now);
+        if tcp.rst() {
+            chunks.extend(close(stream, now));
+        }
+        self.chunks.extend(chunks);
+        self.dispatch(py)?;
+        Ok(true)
+    }
+
+    /// Close all connections, e.g. at the end of a capture
+    ///
+    /// Held data is delivered with gaps for what is missing, followed by
+    /// "end" chunks.
+    ///
+    /// Raises:
+    ///     Exception: Whatever the on_chunk callback raises
+    pub fn flush(&mut self, py: Python<'_>) -> PyResult<()> {
+        let mut streams: Vec<Stream> = self.streams.drain().map(|(_, stream)| stream).collect();
+        streams.sort_by(|a, b| a.last_seen.total_cmp(&b.last_seen));
+        for mut stream in streams {
+            let now = stream.last_seen;
+            let chunks = close(&mut stream, now);
+            self.chunks.extend(chunks);
+        }
+        self.dispatch(py)
+    }
+
+    /// Take the chunks delivered since the last call
+    ///
+    /// Always empty with an on_chunk callback.
+    ///
+    /// Returns:
+    ///     list[StreamChunk]: The chunks, in the order they were delivered
+    pub fn chunks(&mut self) -> Vec<StreamChunk> {
+        self.chunks.drain(..).collect()
+    }
+
+    /// Get the number of open connections
+    pub fn __len__(&self) -> usize {
+        self.streams.values().filter(|stream| !stream.is_closed()).count()
+    }
+}
+
+impl TcpReassembler {
+    /// Close every connection without packets for longer than the timeout
+    fn sweep(&mut self, now: f64) {
+        let timeout = self.timeout;
+        let mut idle: Vec<Endpoints> = self.streams
+            .iter()
+            .filter(|(_, stream)| now - stream.last_seen > timeout)
+            .map(|(key, _)| key.clone())
+            .collect();
+        idle.sort_by(|a, b| self.streams[a].last_seen.total_cmp(&self.streams[b].last_seen));
+        for key in idle {
+            if let Some(mut stream) = self.streams.remove(&key) {
+                let last_seen = stream.last_seen;
+                let chunks = close(&mut stream, last_seen);
+                self.chunks.extend(chunks);
+            }
+        }
+    }
+
+    /// Hand the held chunks to the callback, if there is one
+    fn dispatch(&mut self, py: Python<'_>) -> PyResult<()> {
+        let callback = match &self.on_chunk {
+            Some(callback) => callback.clone_ref(py),
+            None => return Ok(()),
+        };
+        while let Some(chunk) = self.chunks.pop_front() {
+            callback.call1(py, (chunk,))?;
+        }
+        Ok(())
+    }
+}
+
+/// End both directions of a stream, client first
+fn close(stream: &mut Stream, timestamp: f64) -> Vec<StreamChunk> {
+    let mut chunks = Vec::new();
+    for from_client in [true, false] {
+        let mut events = Vec::new();
+        stream.halves[usize::from(!from_client)].close(&mut events);
+        chunks.extend(stream_chunks(stream, from_client, events, timestamp));
+    }
+    chunks
+}
+
+/// Turn the events of one direction into chunks
+fn stream_chunks(stream: &Stream, from_client: bool, events: Vec<Event>, timestamp: f64) -> Vec<StreamChunk> {
+    let client = &stream.client;
+    events
+        .into_iter()
+        .map(|event| {
+            let (kind, offset, length, data) = match event {
+                Event::Data(offset, data) => ("data", offset, data.len() as u64, data),
+                Event::Gap(offset, length) => ("gap", offset, length, Vec::new()),
+                Event::End(offset) => ("end", offset, 0, Vec::new()),
+            };
+            StreamChunk {
+                kind,
+                from_client,
+                client: client.source.clone(),
+                client_port: client.source_port,
+                server: client.destination.clone(),
+                server_port: client.destination_port,
+                offset,
+                length,
+                data,
+                timestamp,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::decode_frame;
+
+    /// Kind, offset, length and data of each event
+    fn describe(events: Vec<Event>) -> Vec<(&'static str, u64, u64, Vec<u8>)> {
+        events
+            .into_iter()
+            .map(|event| match event {
+                Event::Data(offset, data) => ("data", offset, data.len() as u64, data),
+                Event::Gap(offset, length) => ("gap", offset, length, Vec::new()),
+                Event::End(offset) => ("end", offset, 0, Vec::new()),
+            })
+            .collect()
+    }
+
+    /// Insert a segment by sequence number and deliver what continues the
+    /// stream, as the reassembler does for each packet
+    fn segment(half: &mut HalfStream, seq: u32, data: &[u8], events: &mut Vec<Event>) {
+        let position = half.position(seq);
+        half.insert(position, data);
+        let mut delivered = Vec::new();
+        half.deliver(false, &mut delivered);
+        events.extend(delivered);
+    }
+
+    #[test]
+    fn position_follows_sequence_wraparound() {
+        let mut half = HalfStream::default();
+        assert_eq!(half.position(u32::MAX - 1), 0);
+        half.advance(4);
+        assert_eq!(half.next_seq, Some(2));
+        assert_eq!(half.position(2), 4);
+        assert_eq!(half.position(u32::MAX), 1);
+        assert_eq!(half.position(10), 12);
+    }
+
+    #[test]
+    fn data_across_wraparound_is_delivered_in_order() {
+        let mut half = HalfStream::default();
+        let mut events = Vec::new();
+        segment(&mut half, u32::MAX - 2, b"abc", &mut events);
+        segment(&mut half, 3, b"ghi", &mut events);
+        segment(&mut half, 0, b"def", &mut events);
+        let data: Vec<u8> = describe(events).into_iter().flat_map(|(_, _, _, data)| data).collect();
+        assert_eq!(data, b"abcdefghi");
+    }
+
+    #[test]
+    fn overlaps_keep_the_bytes_that_arrived_first() {
+        let mut half = HalfStream::default();
+        let mut events = Vec::new();
+        segment(&mut half, 100, b"hello", &mut events);
+        // Held ahead of the stream, then covered partly by a later segment
+        segment(&mut half, 108, b"LD!", &mut events);
+        segment(&mut half, 103, b"LOworXX", &mut events);
+        assert_eq!(half.pending_bytes, 0);
+        assert_eq!(describe(events), vec![
+            ("data", 0, 5, b"hello".to_vec()),
+            ("data", 5, 6, b"worLD!".to_vec()),
+        ]);
+    }
+
+    #[test]
+    fn retransmissions_are_not_delivered_again() {
+        let mut half = HalfStream::default();
+        let mut events = Vec::new();
+        segment(&mut half, 1, b"abcd", &mut events);
+        segment(&mut half, 1, b"ABCD", &mut events);
+        segment(&mut half, 3, b"CDef", &mut events);
+        assert_eq!(describe(events), vec![
+            ("data", 0, 4, b"abcd".to_vec()),
+            ("data", 4, 2, b"ef".to_vec()),
+        ]);
+    }
+
+    #[test]
+    fn skipping_gaps_emits_gap_events() {
+        let mut half = HalfStream::default();
+        let mut events = Vec::new();
+        segment(&mut half, 0, b"ab", &mut events);
+        segment(&mut half, 5, b"fg", &mut events);
+        segment(&mut half, 10, b"k", &mut events);
+        assert_eq!(half.pending_bytes, 3);
+        half.deliver(true, &mut events);
+        assert_eq!(half.pending_bytes, 0);
+        assert_eq!(describe(events), vec![
+            ("data", 0, 2, b"ab".to_vec()),
+            ("gap", 2, 3, Vec::new()),
+            ("data", 5, 2, b"fg".to_vec()),
+            ("gap", 7, 3, Vec::new()),
+            ("data", 10, 1, b"k".to_vec()),
+        ]);
+    }
+
+    #[test]
+    fn fin_ends_the_direction_once_reached() {
+        let mut half = HalfStream::default();
+        let mut events = Vec::new();
+        segment(&mut half, 0, b"ab", &mut events);
+        half.fin = Some(4);
+        half.deliver(false, &mut events);
+        assert!(!half.ended);
+        segment(&mut half, 2, b"cd", &mut events);
+        assert!(half.ended);
+        // Nothing is taken once the direction ended
+        segment(&mut half, 4, b"ef", &mut events);
+        assert_eq!(describe(events), vec![
+            ("data", 0, 2, b"ab".to_vec()),
+            ("data", 2, 2, b"cd".to_vec()),
+            ("end", 4, 0, Vec::new()),
+        ]);
+    }
+
+    #[test]
+    fn close_fills_missing_bytes_up_to_the_fin() {
+        let mut half = HalfStream::default();
+        let mut events = Vec::new();
+        segment(&mut half, 0, b"ab", &mut events);
+        segment(&mut half, 4, b"ef", &mut events);
+        half.fin = Some(8);
+        half.close(&mut events);
+        assert_eq!(describe(events), vec![
+            ("data", 0, 2, b"ab".to_vec()),
+            ("gap", 2, 2, Vec::new()),
+            ("data", 4, 2, b"ef".to_vec()),
+            ("gap", 6, 2, Vec::new()),
+            ("end", 8, 0, Vec::new()),
+        ]);
+    }
+
+    /// Decode an Ethernet frame with a TCP segment between 10.0.0.1:1000,
+    /// the client, and 10.0.0.2:80, with the flags given as letters
+    fn tcp_packet(from_client: bool, flags: &str, seq: u32, ack: u32, payload: &[u8]) -> ParsedPacket {
+        let (client, server) = ([10, 0, 0, 1], [10, 0, 0, 2]);
+        let (source, destination, source_port, destination_port) = if from_client {
+            (client, server, 1000, 80)
+        } else {
+            (server, client, 80, 1000)
+        };
+        let mut builder = etherparse::PacketBuilder::ethernet2([2, 0, 0, 0, 0, 1], [2, 0, 0, 0, 0, 2])
+            .ipv4(source, destination, 64)
+            .tcp(source_port, destination_port, seq, 64240);
+        if flags.contains('S') {
+            builder = builder.syn();
+        }
+        if flags.contains('A') {
+            builder = builder.ack(ack);
+        }
+        if flags.contains('F') {
+            builder = builder.fin();
+        }
+        let mut frame = Vec::new();
+        builder.write(&mut frame, payload).unwrap();
+        decode_frame(&frame, None)
+    }
+
+    #[test]
+    fn closed_connections_ignore_late_segments() {
+        pyo3::prepare_freethreaded_python();
+        Python::with_gil(|py| {
+            let mut reassembler = TcpReassembler::new(None, 1024 * 1024, 300.0).unwrap();
+            let packets = [
+                tcp_packet(true, "S", 100, 0, b""),
+                tcp_packet(false, "SA", 500, 101, b""),
+                tcp_packet(true, "A", 101, 501, b""),
+                tcp_packet(true, "A", 101, 501, b"hello"),
+                tcp_packet(true, "FA", 106, 501, b""),
+                tcp_packet(false, "FA", 501, 107, b""),
+                // Both directions ended: the last ACK and a retransmitted
+                // FIN don't open a new stream
+                tcp_packet(true, "A", 107, 502, b""),
+                tcp_packet(false, "FA", 501, 107, b""),
+            ];
+            for packet in &packets {
+                assert!(reassembler.add(py, packet).unwrap());
+            }
+            assert_eq!(reassembler.__len__(), 0);
+            reassembler.flush(py).unwrap();
+
+            let chunks: Vec<_> = reassembler
+                .chunks()
+                .into_iter()
+                .map(|chunk| (chunk.kind, chunk.from_client, chunk.offset, chunk.data, chunk.client_port))
+                .collect();
+            assert_eq!(chunks, vec![
+                ("data", true, 0, b"hello".to_vec(), 1000),
+                ("end", true, 5, Vec::new(), 1000),
+                ("end", false, 0, Vec::new(), 1000),
+            ]);
+
+            // A new SYN on the same ports is a new connection
+            reassembler.add(py, &tcp_packet(true, "S", 9000, 0, b"")).unwrap();
+            reassembler.add(py, &tcp_packet(true, "A", 9001, 0, b"again")).unwrap();
+            assert_eq!(reassembler.__len__(), 1);
+            let chunks = reassembler.chunks();
+            assert_eq!((chunks.len(), chunks[0].offset, chunks[0].data.as_slice()), (1, 0, &b"again"[..]));
+        });
+    }
+}
@@ -0,0 +1,106 @@
+//! Capture file writers
+//!
+//! This module contains classes for writing packets back out as PCAP or
+//! pcapng files.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+use pyo3::prelude::*;
+use crate::models::ParsedPacket;
+
+// Declare submodules
+mod pcap;
+mod pcapng;
+
+// Re-export writer classes
+pub use pcap::PcapWriter;
+pub use pcapng::PcapNgWriter;
+
+/// A packet handed to a writer: either raw frame bytes or a ParsedPacket
+#[derive(FromPyObject)]
+pub enum PacketInput<'py> {
+    Packet(PyRef<'py, ParsedPacket>),
+    Bytes(Vec<u8>),
+}
+
+/// A frame ready to be written, with its timestamp and original length
+pub(crate) struct OutputFrame {
+    pub ts_sec: i64,
+    pub ts_nsec: u32,
+    pub original_length: u32,
+    pub data: Vec<u8>,
+}
+
+impl OutputFrame {
+    /// Resolve the frame bytes and record metadata for a write() call
+    ///
+    /// The timestamp defaults to the capture timestamp of a ParsedPacket and
+    /// to the current time for raw bytes. The original length defaults to
+    /// the packet's original length (or the data length, whichever is
+    /// larger). The data is then cut down to `snaplen` if it is non-zero.
+    ///
+    /// Raises:
+    ///     ValueError: If the timestamp is NaN or infinite, or the original
+    ///         length is smaller than the data
+    pub fn resolve(
+        packet: &PacketInput<'_>,
+        timestamp: Option<f64>,
+        original_length: Option<u32>,
+        snaplen: u32,
+    ) -> PyResult<Self> {
+        let (mut data, packet_ts, packet_length) = match packet {
+            PacketInput::Packet(parsed) => (
+                parsed.wire_bytes(),
+                Some((parsed.ts_sec, parsed.ts_nsec)),
+                parsed.original_length,
+            ),
+            PacketInput::Bytes(bytes) => (bytes.clone(), None, 0),
+        };
+
+        let (ts_sec, ts_nsec) = match (timestamp, packet_ts) {
+            (Some(timestamp), _) => split_timestamp(timestamp)?,
+            (None, Some(packet_ts)) => packet_ts,
+            (None, None) => now(),
+        };
+        let data_length = data.len() as u32;
+        let original_length = match original_length {
+            Some(length) if length < data_length => {
+                return Err(pyo3::exceptions::PyValueError::new_err(format!(
+                    "Original length {} is smaller than the {} bytes of data",
+                    length, data_length
+                )));
+            },
+            Some(length) => length,
+            None => packet_length.max(data_length),
+        };
+
+        if snaplen != 0 && data.len() > snaplen as usize {
+            data.truncate(snaplen as usize);
+        }
+
+        Ok(Self { ts_sec, ts_nsec, original_length, data })
+    }
+}
+
+/// Split seconds since the Unix epoch into whole seconds and nanoseconds
+///
+/// Raises:
+///     ValueError: If the timestamp is NaN or infinite
+fn split_timestamp(timestamp: f64) -> PyResult<(i64, u32)> {
+    if !timestamp.is_finite() {
+        return Err(pyo3::exceptions::PyValueError::new_err(format!(
+            "Invalid timestamp: {} (expected a finite number of seconds)",
+            timestamp
+        )));
+    }
+    let seconds = timestamp.floor();
+    let nanos = ((timestamp - seconds) * 1e9).round().min(999_999_999.0);
+    Ok((seconds as i64, nanos as u32))
+}
+
+/// Current time as whole seconds and nanoseconds since the Unix epoch
+fn now() -> (i64, u32) {
+    match SystemTime::now().duration_since(UNIX_EPOCH) {
+        Ok(elapsed) => (elapsed.as_secs() as i64, elapsed.subsec_nanos()),
+        Err(_) => (0, 0),
+    }
+}
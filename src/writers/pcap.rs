@@ -0,0 +1,166 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use pyo3::prelude::*;
+use crate::writers::{OutputFrame, PacketInput};
+
+/// Writes packets to a classic PCAP file
+///
+/// Accepts raw frame bytes or ParsedPackets. Works as a context manager
+/// and closes the file on exit:
+///
+///     with PcapWriter("out.pcap") as writer:
+///         for packet in PcapReader("in.pcap"):
+///             if packet.has_tcp():
+///                 writer.write(packet)
+///
+/// Args:
+///     path: Path of the file to create (an existing file is overwritten)
+///     link_type: Link-layer header type of every packet (default: 1, Ethernet)
+///     snaplen: Maximum number of bytes stored per packet (default: 65535)
+///     nanosecond: Store nanosecond instead of microsecond timestamps (default: False)
+///
+/// Raises:
+///     IOError: If the file cannot be created
+#[pyclass]
+pub struct PcapWriter {
+    // The file is dropped (after flushing) on close()
+    file: Option<BufWriter<File>>,
+    snaplen: u32,
+    nanosecond: bool,
+}
+
+#[pymethods]
+impl PcapWriter {
+    /// Create the file and write the pcap global header
+    #[new]
+    #[pyo3(signature = (path, link_type = 1, snaplen = 65535, nanosecond = false))]
+    pub fn new(path: String, link_type: u32, snaplen: u32, nanosecond: bool) -> PyResult<Self> {
+        let file = File::create(&path).map_err(|e| {
+            pyo3::exceptions::PyIOError::new_err(format!("Failed to create pcap file: {}", e))
+        })?;
+
+        let mut writer = Self {
+            file: Some(BufWriter::new(file)),
+            snaplen,
+            nanosecond,
+        };
+
+        // Global header, written in native byte order like libpcap does
+        let magic: u32 = if nanosecond { 0xa1b2_3c4d } else { 0xa1b2_c3d4 };
+        let mut header = Vec::with_capacity(24);
+        header.extend_from_slice(&magic.to_ne_bytes());
+        header.extend_from_slice(&2u16.to_ne_bytes()); // major version
+        header.extend_from_slice(&4u16.to_ne_bytes()); // minor version
+        header.extend_from_slice(&0i32.to_ne_bytes()); // timezone offset (unused)
+        header.extend_from_slice(&0u32.to_ne_bytes()); // timestamp accuracy (unused)
+        header.extend_from_slice(&snaplen.to_ne_bytes());
+        header.extend_from_slice(&link_type.to_ne_bytes());
+        writer.write_all(&header)?;
+
+        Ok(writer)
+    }
+
+    /// Write one packet record
+    ///
+    /// Args:
+    ///     packet: Raw frame bytes or a ParsedPacket
+    ///     timestamp: Capture time in seconds since the Unix epoch (default:
+    ///         the ParsedPacket's own timestamp, or the current time for bytes)
+    ///     original_length: Length of the frame on the wire, at least the
+    ///         data length (default: the ParsedPacket's original length, or
+    ///         the data length)
+    ///
+    /// Raises:
+    ///     ValueError: If the writer has been closed, the original length is
+    ///         smaller than the data, or the timestamp is not finite or
+    ///         outside 1970-2106, which pcap can't store
+    ///     IOError: If writing fails
+    #[pyo3(signature = (packet, timestamp = None, original_length = None))]
+    pub fn write(
+        &mut self,
+        packet: PacketInput<'_>,
+        timestamp: Option<f64>,
+        original_length: Option<u32>,
+    ) -> PyResult<()> {
+        let frame = OutputFrame::resolve(&packet, timestamp, original_length, self.snaplen)?;
+
+        let ts_sec = u32::try_from(frame.ts_sec).map_err(|_| {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "Timestamp {} is out of range for pcap files (0-{} seconds since the epoch)",
+                frame.ts_sec,
+                u32::MAX
+            ))
+        })?;
+        let fraction = if self.nanosecond { frame.ts_nsec } else { frame.ts_nsec / 1000 };
+        let mut record = Vec::with_capacity(16 + frame.data.len());
+        record.extend_from_slice(&ts_sec.to_ne_bytes());
+        record.extend_from_slice(&fraction.to_ne_bytes());
+        record.extend_from_slice(&(frame.data.len() as u32).to_ne_bytes());
+        record.extend_from_slice(&frame.original_length.to_ne_bytes());
+        record.extend_from_slice(&frame.data);
+        self.write_all(&record)
+    }
+
+    /// Flush buffered records to the file
+    ///
+    /// Raises:
+    ///     ValueError: If the writer has been closed
+    ///     IOError: If writing fails
+    pub fn flush(&mut self) -> PyResult<()> {
+        self.file()?.flush().map_err(write_error)
+    }
+
+    /// Flush and close the file. Calling close() more than once is allowed.
+    ///
+    /// Raises:
+    ///     IOError: If writing the remaining records fails
+    pub fn close(&mut self) -> PyResult<()> {
+        match self.file.take() {
+            Some(mut file) => file.flush().map_err(write_error),
+            None => Ok(()),
+        }
+    }
+
+    /// Check if the writer has been closed
+    #[getter]
+    pub fn closed(&self) -> bool {
+        self.file.is_none()
+    }
+
+    /// Enter the runtime context (returns the writer itself)
+    pub fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Exit the runtime context, closing the writer
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    pub fn __exit__(
+        &mut self,
+        _exc_type: Option<PyObject>,
+        _exc_value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> PyResult<bool> {
+        self.close()?;
+        // Never swallow exceptions raised inside the with block
+        Ok(false)
+    }
+}
+
+impl PcapWriter {
+    /// Get the open file or fail if the writer was closed
+    fn file(&mut self) -> PyResult<&mut BufWriter<File>> {
+        self.file.as_mut().ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err("I/O operation on closed PcapWriter")
+        })
+    }
+
+    /// Write bytes to the file
+    fn write_all(&mut self, bytes: &[u8]) -> PyResult<()> {
+        self.file()?.write_all(bytes).map_err(write_error)
+    }
+}
+
+/// Convert a write failure into a Python exception
+fn write_error(e: std::io::Error) -> PyErr {
+    pyo3::exceptions::PyIOError::new_err(format!("Failed to write pcap file: {}", e))
+}
@@ -0,0 +1,317 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use pyo3::prelude::*;
+use crate::writers::{OutputFrame, PacketInput};
+
+// Block types
+const SECTION_HEADER_BLOCK: u32 = 0x0a0d_0d0a;
+const INTERFACE_DESCRIPTION_BLOCK: u32 = 0x0000_0001;
+const ENHANCED_PACKET_BLOCK: u32 = 0x0000_0006;
+
+// Option codes
+const OPT_COMMENT: u16 = 1;
+const SHB_USERAPPL: u16 = 4;
+const IF_NAME: u16 = 2;
+const IF_DESCRIPTION: u16 = 3;
+const IF_TSRESOL: u16 = 9;
+const EPB_FLAGS: u16 = 2;
+
+/// if_tsresol value for nanosecond timestamps (10^-9)
+const NANOSECOND_RESOLUTION: u8 = 9;
+
+/// Writes packets to a pcapng file
+///
+/// The file gets one section with a first interface built from the
+/// constructor arguments; more interfaces can be added with
+/// add_interface(). Timestamps are stored with nanosecond resolution.
+///
+/// Args:
+///     path: Path of the file to create (an existing file is overwritten)
+///     link_type: Link-layer header type of interface 0 (default: 1, Ethernet)
+///     snaplen: Maximum number of bytes stored per packet on interface 0
+///         (default: 0, unlimited)
+///     interface_name: Name of interface 0 (default: None)
+///     comment: Comment attached to the section (default: None)
+///
+/// Raises:
+///     ValueError: If the comment or interface name is longer than 65535 bytes
+///     IOError: If the file cannot be created
+#[pyclass]
+pub struct PcapNgWriter {
+    // The file is dropped (after flushing) on close()
+    file: Option<BufWriter<File>>,
+    // Snaplen of each interface, indexed by interface ID
+    snaplens: Vec<u32>,
+}
+
+#[pymethods]
+impl PcapNgWriter {
+    /// Create the file and write the section header and first interface
+    #[new]
+    #[pyo3(signature = (path, link_type = 1, snaplen = 0, interface_name = None, comment = None))]
+    pub fn new(
+        path: String,
+        link_type: u16,
+        snaplen: u32,
+        interface_name: Option<String>,
+        comment: Option<String>,
+    ) -> PyResult<Self> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&0x1a2b_3c4du32.to_ne_bytes()); // byte-order magic
+        body.extend_from_slice(&1u16.to_ne_bytes()); // major version
+        body.extend_from_slice(&0u16.to_ne_bytes()); // minor version
+        body.extend_from_slice(&(-1i64).to_ne_bytes()); // section length (unknown)
+        let mut options = Vec::new();
+        if let Some(comment) = &comment {
+            push_option(&mut options, OPT_COMMENT, comment.as_bytes())?;
+        }
+        push_option(&mut options, SHB_USERAPPL, b"pytherparse")?;
+        finish_options(&mut body, options);
+
+        let file = File::create(&path).map_err(|e| {
+            pyo3::exceptions::PyIOError::new_err(format!("Failed to create pcapng file: {}", e))
+        })?;
+        let mut writer = Self {
+            file: Some(BufWriter::new(file)),
+            snaplens: Vec::new(),
+        };
+        writer.write_block(SECTION_HEADER_BLOCK, &body)?;
+
+        writer.add_interface(link_type, snaplen, interface_name, None)?;
+        Ok(writer)
+    }
+
+    /// Declare another capture interface
+    ///
+    /// Args:
+    ///     link_type: Link-layer header type of the interface
+    ///     snaplen: Maximum number of bytes stored per packet (default: 0, unlimited)
+    ///     name: Interface name (default: None)
+    ///     description: Interface description (default: None)
+    ///
+    /// Returns:
+    ///     int: The ID to pass as interface_id to write()
+    ///
+    /// Raises:
+    ///     ValueError: If the writer has been closed or the name or
+    ///         description is longer than 65535 bytes
+    ///     IOError: If writing fails
+    #[pyo3(signature = (link_type, snaplen = 0, name = None, description = None))]
+    pub fn add_interface(
+        &mut self,
+        link_type: u16,
+        snaplen: u32,
+        name: Option<String>,
+        description: Option<String>,
+    ) -> PyResult<u32> {
+        let mut body = Vec::new();
+        body.extend_from_slice(&link_type.to_ne_bytes());
+        body.extend_from_slice(&0u16.to_ne_bytes()); // reserved
+        body.extend_from_slice(&snaplen.to_ne_bytes());
+        let mut options = Vec::new();
+        if let Some(name) = &name {
+            push_option(&mut options, IF_NAME, name.as_bytes())?;
+        }
+        if let Some(description) = &description {
+            push_option(&mut options, IF_DESCRIPTION, description.as_bytes())?;
+        }
+        push_option(&mut options, IF_TSRESOL, &[NANOSECOND_RESOLUTION])?;
+        finish_options(&mut body, options);
+        self.write_block(INTERFACE_DESCRIPTION_BLOCK, &body)?;
+
+        self.snaplens.push(snaplen);
+        Ok(self.snaplens.len() as u32 - 1)
+    }
+
+    /// Write one packet as an Enhanced Packet Block
+    ///
+    /// Args:
+    ///     packet: Raw frame bytes or a ParsedPacket
+    ///     timestamp: Capture time in seconds since the Unix epoch (default:
+    ///         the ParsedPacket's own timestamp, or the current time for bytes)
+    ///     original_length: Length of the frame on the wire, at least the
+    ///         data length (default: the ParsedPacket's original length, or
+    ///         the data length)
+    ///     interface_id: Interface the packet was captured on (default: 0)
+    ///     comments: Comments to attach (default: the ParsedPacket's comments)
+    ///     flags: epb_flags value (default: the ParsedPacket's packet_flags)
+    ///
+    /// Raises:
+    ///     ValueError: If the writer has been closed, the interface is
+    ///         unknown, the original length is smaller than the data, a
+    ///         comment is longer than 65535 bytes or the timestamp is not
+    ///         finite, before 1970 or too far in the future for 64-bit
+    ///         nanoseconds
+    ///     IOError: If writing fails
+    #[pyo3(signature = (packet, timestamp = None, original_length = None, interface_id = 0, comments = None, flags = None))]
+    pub fn write(
+        &mut self,
+        packet: PacketInput<'_>,
+        timestamp: Option<f64>,
+        original_length: Option<u32>,
+        interface_id: u32,
+        comments: Option<Vec<String>>,
+        flags: Option<u32>,
+    ) -> PyResult<()> {
+        let snaplen = match self.snaplens.get(interface_id as usize) {
+            Some(snaplen) => *snaplen,
+            None => {
+                return Err(pyo3::exceptions::PyValueError::new_err(
+                    format!("Unknown interface ID: {}", interface_id)
+                ));
+            }
+        };
+        let frame = OutputFrame::resolve(&packet, timestamp, original_length, snaplen)?;
+
+        // Carry pcapng metadata over from parsed packets unless overridden
+        let (comments, flags) = match &packet {
+            PacketInput::Packet(parsed) => (
+                comments.unwrap_or_else(|| parsed.comments.clone()),
+                flags.or(parsed.packet_flags),
+            ),
+            PacketInput::Bytes(_) => (comments.unwrap_or_default(), flags),
+        };
+
+        // Nanoseconds since the epoch, split into high and low words
+        let ticks = u64::try_from(frame.ts_sec)
+            .ok()
+            .and_then(|seconds| seconds.checked_mul(1_000_000_000))
+            .and_then(|nanoseconds| nanoseconds.checked_add(u64::from(frame.ts_nsec)))
+            .ok_or_else(|| {
+                pyo3::exceptions::PyValueError::new_err(format!(
+                    "Timestamp {} is out of range for pcapng files (0-{} seconds since the epoch)",
+                    frame.ts_sec,
+                    u64::MAX / 1_000_000_000 - 1
+                ))
+            })?;
+
+        let mut body = Vec::with_capacity(32 + frame.data.len());
+        body.extend_from_slice(&interface_id.to_ne_bytes());
+        body.extend_from_slice(&((ticks >> 32) as u32).to_ne_bytes());
+        body.extend_from_slice(&(ticks as u32).to_ne_bytes());
+        body.extend_from_slice(&(frame.data.len() as u32).to_ne_bytes());
+        body.extend_from_slice(&frame.original_length.to_ne_bytes());
+        body.extend_from_slice(&frame.data);
+        pad(&mut body);
+        let mut options = Vec::new();
+        for comment in &comments {
+            push_option(&mut options, OPT_COMMENT, comment.as_bytes())?;
+        }
+        if let Some(flags) = flags {
+            push_option(&mut options, EPB_FLAGS, &flags.to_ne_bytes())?;
+        }
+        finish_options(&mut body, options);
+        self.write_block(ENHANCED_PACKET_BLOCK, &body)
+    }
+
+    /// Flush buffered blocks to the file
+    ///
+    /// Raises:
+    ///     ValueError: If the writer has been closed
+    ///     IOError: If writing fails
+    pub fn flush(&mut self) -> PyResult<()> {
+        self.file()?.flush().map_err(write_error)
+    }
+
+    /// Flush and close the file. Calling close() more than once is allowed.
+    ///
+    /// Raises:
+    ///     IOError: If writing the remaining blocks fails
+    pub fn close(&mut self) -> PyResult<()> {
+        match self.file.take() {
+            Some(mut file) => file.flush().map_err(write_error),
+            None => Ok(()),
+        }
+    }
+
+    /// Check if the writer has been closed
+    #[getter]
+    pub fn closed(&self) -> bool {
+        self.file.is_none()
+    }
+
+    /// Enter the runtime context (returns the writer itself)
+    pub fn __enter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Exit the runtime context, closing the writer
+    #[pyo3(signature = (_exc_type=None, _exc_value=None, _traceback=None))]
+    pub fn __exit__(
+        &mut self,
+        _exc_type: Option<PyObject>,
+        _exc_value: Option<PyObject>,
+        _traceback: Option<PyObject>,
+    ) -> PyResult<bool> {
+        self.close()?;
+        // Never swallow exceptions raised inside the with block
+        Ok(false)
+    }
+}
+
+impl PcapNgWriter {
+    /// Get the open file or fail if the writer was closed
+    fn file(&mut self) -> PyResult<&mut BufWriter<File>> {
+        self.file.as_mut().ok_or_else(|| {
+            pyo3::exceptions::PyValueError::new_err("I/O operation on closed PcapNgWriter")
+        })
+    }
+
+    /// Frame a block body with its type and both length fields and write it
+    ///
+    /// Raises:
+    ///     ValueError: If the block doesn't fit into the 32-bit length field
+    fn write_block(&mut self, block_type: u32, body: &[u8]) -> PyResult<()> {
+        let total_length = u32::try_from(body.len() + 12).map_err(|_| {
+            pyo3::exceptions::PyValueError::new_err(format!(
+                "Block of {} bytes is too large for a pcapng file",
+                body.len() + 12
+            ))
+        })?;
+        let mut block = Vec::with_capacity(total_length as usize);
+        block.extend_from_slice(&block_type.to_ne_bytes());
+        block.extend_from_slice(&total_length.to_ne_bytes());
+        block.extend_from_slice(body);
+        block.extend_from_slice(&total_length.to_ne_bytes());
+        self.file()?.write_all(&block).map_err(write_error)
+    }
+}
+
+/// Append one option (code, length, padded value) to an options area
+///
+/// Raises:
+///     ValueError: If the value is longer than 65535 bytes
+fn push_option(options: &mut Vec<u8>, code: u16, value: &[u8]) -> PyResult<()> {
+    let length = u16::try_from(value.len()).map_err(|_| {
+        pyo3::exceptions::PyValueError::new_err(format!(
+            "pcapng option {} is {} bytes long, at most 65535 are allowed",
+            code,
+            value.len()
+        ))
+    })?;
+    options.extend_from_slice(&code.to_ne_bytes());
+    options.extend_from_slice(&length.to_ne_bytes());
+    options.extend_from_slice(value);
+    pad(options);
+    Ok(())
+}
+
+/// Append an options area, terminated by opt_endofopt, to a block body
+fn finish_options(body: &mut Vec<u8>, options: Vec<u8>) {
+    if !options.is_empty() {
+        body.extend(options);
+        body.extend_from_slice(&[0u8; 4]);
+    }
+}
+
+/// Pad a buffer with zeros to a multiple of 4 bytes
+fn pad(buf: &mut Vec<u8>) {
+    while !buf.len().is_multiple_of(4) {
+        buf.push(0);
+    }
+}
+
+/// Convert a write failure into a Python exception
+fn write_error(e: std::io::Error) -> PyErr {
+    pyo3::exceptions::PyIOError::new_err(format!("Failed to write pcapng file: {}", e))
+}